@@ -1,40 +1,60 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
 
 use crate::{App, errors::AppStatus};
 use alloy::{hex, signers::k256::ecdsa::SigningKey, transports::http::reqwest::Url};
-use common::{constants::SK, parse_sk};
-use runners::Runner::{self, *};
+use common::{constants::SK, errors::Error, is_supported_rpc, parse_sk};
+use runners::{
+    Runner::{self, *},
+    config::RunnerConfig,
+    network::Network,
+};
 
 impl App {
     // Helper function to check if a runner type is valid
     fn is_valid_runner(&self, runner: &str) -> bool {
         [
-            "al", "blob", "eip1559", "eip7702", "legacy", "random",
+            "al", "blob", "eip1559", "eip7702", "engine", "envelope", "legacy", "random",
+            "scenario",
         ]
         .contains(&runner)
     }
 
-    // Helper function to check if a scope is valid
+    // Helper function to check if a runner instance name is valid. Names of
+    // runner types can only be used by instances of that same type.
+    fn is_valid_name(&self, name: &str, runner: &str) -> bool {
+        !name.is_empty()
+            && name != "global"
+            && !name.contains('=')
+            && (name == runner || !self.is_valid_runner(name))
+    }
+
+    // Helper function to check if a scope is valid. Besides `global` and the
+    // runner types, the name of any known runner instance is a valid scope.
     fn is_valid_scope(&self, scope: &str) -> bool {
-        [
-            "global", "al", "blob", "eip1559", "eip7702", "legacy", "random",
-        ]
-        .contains(&scope)
+        scope == "global"
+            || self.is_valid_runner(scope)
+            || self.active_runners.contains_key(scope)
+            || self.runner_rpcs.contains_key(scope)
+            || self.runner_sks.contains_key(scope)
+            || self.runner_seeds.contains_key(scope)
+            || self.runner_workers.contains_key(scope)
+            || self.runner_configs.contains_key(scope)
     }
 
     // Helper function to check if a parameter is valid
     fn is_valid_param(&self, param: &str) -> bool {
         [
-            "rpc", "sk", "seed", "chainid",
+            "rpc", "sk", "seed", "workers", "network",
         ]
         .contains(&param)
+            || RunnerConfig::PARAMS.contains(&param)
     }
 
     // Helper function to handle setting globalconfig values
     fn handle_global_set(&mut self, param: &str, value: &str) -> Result<(), AppStatus> {
         match param {
             "rpc" => {
-                if let Ok(url) = Url::parse(value) {
+                if let Some(url) = Url::parse(value).ok().filter(is_supported_rpc) {
                     if self.rpc_url == url {
                         self.print(&format!("global rpc url already set to that value"));
                         return Err(AppStatus::RuntimeError);
@@ -72,273 +92,124 @@ impl App {
                     return Err(AppStatus::RuntimeError);
                 }
             }
-            _ => unreachable!(),
+            "workers" => {
+                self.print("workers can only be set per runner");
+                return Err(AppStatus::RuntimeError);
+            }
+            "network" => match value.parse::<Network>() {
+                Ok(network) => {
+                    if self.network.as_ref() == Some(&network) {
+                        self.print("global network already set to that value");
+                        return Err(AppStatus::RuntimeError);
+                    }
+                    // The profile replaces the global RPC URL, key and the
+                    // settings it bundles, which can be set again afterwards
+                    self.rpc_url = network.rpc_url.clone();
+                    self.sk = parse_sk(&network.sk).unwrap();
+                    network.apply(&mut self.config);
+                    self.print(&format!(
+                        "global network set to {} (chain id {})",
+                        network.name, network.chain_id
+                    ));
+                    self.network = Some(network);
+                }
+                Err(e) => {
+                    self.print(&e);
+                    return Err(AppStatus::RuntimeError);
+                }
+            },
+            _ => {
+                if self.config.get(param).as_deref() == Some(value) {
+                    self.print(&format!("global {} already set to that value", param));
+                    return Err(AppStatus::RuntimeError);
+                }
+                if let Err(e) = self.config.set(param, value) {
+                    self.print(&e);
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.print(&format!("global {} set to {}", param, value));
+            }
         }
 
         Ok(())
     }
 
     // Helper function to handle setting per-runner config values
-    fn handle_runner_set(
-        &mut self,
-        runner: &str,
-        param: &str,
-        value: &str,
-    ) -> Result<(), AppStatus> {
-        match runner {
-            "al" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&AL) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(AL, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&AL) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(AL, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&AL) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(AL, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                _ => unreachable!(),
-            },
-            "blob" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&Blob) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(Blob, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&Blob) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(Blob, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&Blob) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(Blob, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                _ => unreachable!(),
-            },
-            "eip1559" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&EIP1559) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(EIP1559, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&EIP1559) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(EIP1559, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&EIP1559) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(EIP1559, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                _ => unreachable!(),
-            },
-            "eip7702" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&EIP7702) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(EIP7702, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&EIP7702) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(EIP7702, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
+    fn handle_runner_set(&mut self, name: &str, param: &str, value: &str) -> Result<(), AppStatus> {
+        let name = name.to_string();
+        match param {
+            "rpc" => {
+                if let Some(url) = Url::parse(value).ok().filter(is_supported_rpc) {
+                    if self.runner_rpcs.get(&name) == Some(&url) {
+                        self.print(&format!("{} rpc url already set to that value", name));
                         return Err(AppStatus::RuntimeError);
                     }
+                    self.runner_rpcs.insert(name.clone(), url);
+                    self.print(&format!("{} rpc url set to {}", name, value));
+                } else {
+                    self.print(&format!("invalid rpc url: {}", value));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&EIP7702) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(EIP7702, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
+            }
+            "sk" => {
+                if let Ok(sk) = parse_sk(value) {
+                    if self.runner_sks.get(&name) == Some(&sk) {
+                        self.print(&format!("{} sk already set to that value", name));
                         return Err(AppStatus::RuntimeError);
                     }
+                    self.runner_sks.insert(name.clone(), sk);
+                    self.print(&format!("{} sk set to {}", name, value));
+                } else {
+                    self.print(&format!("invalid sk: {}", value));
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
-            },
-            "legacy" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&Legacy) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(Legacy, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
+            }
+            "seed" => {
+                if let Ok(seed) = value.parse::<u64>() {
+                    if self.runner_seeds.get(&name) == Some(&seed) {
+                        self.print(&format!("{} seed already set to that value", name));
                         return Err(AppStatus::RuntimeError);
                     }
+                    self.runner_seeds.insert(name.clone(), seed);
+                    self.print(&format!("{} seed set to {}", name, value));
+                } else {
+                    self.print(&format!("invalid seed: {}", value));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&Legacy) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(Legacy, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
+            }
+            "workers" => match value.parse::<usize>() {
+                Ok(workers) if workers > 0 => {
+                    if self.runner_workers.get(&name) == Some(&workers) {
+                        self.print(&format!("{} workers already set to that value", name));
                         return Err(AppStatus::RuntimeError);
                     }
+                    self.runner_workers.insert(name.clone(), workers);
+                    self.print(&format!("{} workers set to {}", name, value));
                 }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&Legacy) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(Legacy, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
+                _ => {
+                    self.print(&format!("invalid workers: {}", value));
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
             },
-            "random" => match param {
-                "rpc" => {
-                    if let Ok(url) = Url::parse(value) {
-                        if self.runner_rpcs.get(&Random) == Some(&url) {
-                            self.print(&format!("{} rpc url already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_rpcs.insert(Random, url);
-                        self.print(&format!("{} rpc url set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid rpc url: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                }
-                "sk" => {
-                    if let Ok(sk) = parse_sk(value) {
-                        if self.runner_sks.get(&Random) == Some(&sk) {
-                            self.print(&format!("{} sk already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_sks.insert(Random, sk);
-                        self.print(&format!("{} sk set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid sk: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
+            "network" => {
+                self.print("network can only be set globally");
+                return Err(AppStatus::RuntimeError);
+            }
+            _ => {
+                let mut config = self.runner_configs.get(&name).unwrap_or(&self.config).clone();
+                if self.runner_configs.contains_key(&name)
+                    && config.get(param).as_deref() == Some(value)
+                {
+                    self.print(&format!("{} {} already set to that value", name, param));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "seed" => {
-                    if let Ok(seed) = value.parse::<u64>() {
-                        if self.runner_seeds.get(&Random) == Some(&seed) {
-                            self.print(&format!("{} seed already set to that value", runner));
-                            return Err(AppStatus::RuntimeError);
-                        }
-                        self.runner_seeds.insert(Random, seed);
-                        self.print(&format!("{} seed set to {}", runner, value));
-                    } else {
-                        self.print(&format!("invalid seed: {}", value));
-                        return Err(AppStatus::RuntimeError);
-                    }
+                if let Err(e) = config.set(param, value) {
+                    self.print(&e);
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
-            },
-            _ => unreachable!(),
+                self.runner_configs.insert(name.clone(), config);
+                self.print(&format!("{} {} set to {}", name, param, value));
+            }
         }
 
         Ok(())
@@ -350,13 +221,20 @@ impl App {
             "all" => {
                 let sk = SigningKey::from_slice(hex::decode(SK).unwrap().as_slice()).unwrap();
                 let url = Url::parse("http://localhost:8545").unwrap();
-                if self.rpc_url == url && self.seed == 0 && self.sk == sk {
+                if self.rpc_url == url
+                    && self.seed == 0
+                    && self.sk == sk
+                    && self.config == RunnerConfig::default()
+                    && self.network.is_none()
+                {
                     self.print("global config is already reset");
                     return Err(AppStatus::RuntimeError);
                 }
                 self.rpc_url = url;
                 self.seed = 0;
                 self.sk = sk;
+                self.config = RunnerConfig::default();
+                self.network = None;
             }
             "rpc" => {
                 let url = Url::parse("http://localhost:8545").unwrap();
@@ -381,231 +259,156 @@ impl App {
                 }
                 self.seed = 0;
             }
-            _ => unreachable!(),
+            "workers" => {
+                self.print("workers can only be reset per runner");
+                return Err(AppStatus::RuntimeError);
+            }
+            "network" => {
+                // The settings taken from the profile are kept, only the
+                // profile is forgotten
+                if self.network.is_none() {
+                    self.print("global network is already reset");
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.network = None;
+            }
+            _ => {
+                let default = RunnerConfig::default().get(param).unwrap();
+                if self.config.get(param) == Some(default.clone()) {
+                    self.print(&format!("global {} is already reset", param));
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.config.set(param, &default).unwrap();
+            }
         }
 
         Ok(())
     }
 
-    fn handle_runner_reset(&mut self, runner: &str, param: &str) -> Result<(), AppStatus> {
-        match runner {
-            "al" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&AL) == None
-                        && self.runner_sks.get(&AL) == None
-                        && self.runner_seeds.get(&AL) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&AL);
-                    self.runner_sks.remove(&AL);
-                    self.runner_seeds.remove(&AL);
-                }
-                "rpc" => {
-                    if self.runner_rpcs.get(&AL) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&AL);
-                }
-                "sk" => {
-                    if self.runner_sks.get(&AL) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_sks.remove(&AL);
-                }
-                "seed" => {
-                    if self.runner_seeds.get(&AL) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_seeds.remove(&AL);
-                }
-                _ => unreachable!(),
-            },
-            "blob" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&Blob) == None
-                        && self.runner_sks.get(&Blob) == None
-                        && self.runner_seeds.get(&Blob) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&Blob);
-                    self.runner_sks.remove(&Blob);
-                    self.runner_seeds.remove(&Blob);
-                }
-                "rpc" => {
-                    if self.runner_rpcs.get(&Blob) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&Blob);
-                }
-                "sk" => {
-                    if self.runner_sks.get(&Blob) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_sks.remove(&Blob);
-                }
-                "seed" => {
-                    if self.runner_seeds.get(&Blob) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_seeds.remove(&Blob);
-                }
-                _ => unreachable!(),
-            },
-            "eip1559" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&EIP1559) == None
-                        && self.runner_sks.get(&EIP1559) == None
-                        && self.runner_seeds.get(&EIP1559) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&EIP1559);
-                    self.runner_sks.remove(&EIP1559);
-                    self.runner_seeds.remove(&EIP1559);
-                }
-                "rpc" => {
-                    if self.runner_rpcs.get(&EIP1559) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&EIP1559);
-                }
-                "sk" => {
-                    if self.runner_sks.get(&EIP1559) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_sks.remove(&EIP1559);
-                }
-                "seed" => {
-                    if self.runner_seeds.get(&EIP1559) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_seeds.remove(&EIP1559);
+    fn handle_runner_reset(&mut self, name: &str, param: &str) -> Result<(), AppStatus> {
+        match param {
+            "all" => {
+                if !self.runner_rpcs.contains_key(name)
+                    && !self.runner_sks.contains_key(name)
+                    && !self.runner_seeds.contains_key(name)
+                    && !self.runner_workers.contains_key(name)
+                    && !self.runner_configs.contains_key(name)
+                {
+                    self.print(&format!("{} runner is already reset", name));
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
-            },
-            "eip7702" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&EIP7702) == None
-                        && self.runner_sks.get(&EIP7702) == None
-                        && self.runner_seeds.get(&EIP7702) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&EIP7702);
-                    self.runner_sks.remove(&EIP7702);
-                    self.runner_seeds.remove(&EIP7702);
+                self.runner_rpcs.remove(name);
+                self.runner_sks.remove(name);
+                self.runner_seeds.remove(name);
+                self.runner_workers.remove(name);
+                self.runner_configs.remove(name);
+            }
+            "rpc" => {
+                if !self.runner_rpcs.contains_key(name) {
+                    self.print(&format!("{} runner rpc is already reset", name));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "rpc" => {
-                    if self.runner_rpcs.get(&EIP7702) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&EIP7702);
+                self.runner_rpcs.remove(name);
+            }
+            "sk" => {
+                if !self.runner_sks.contains_key(name) {
+                    self.print(&format!("{} runner sk is already reset", name));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "sk" => {
-                    if self.runner_sks.get(&EIP7702) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_sks.remove(&EIP7702);
+                self.runner_sks.remove(name);
+            }
+            "seed" => {
+                if !self.runner_seeds.contains_key(name) {
+                    self.print(&format!("{} runner seed is already reset", name));
+                    return Err(AppStatus::RuntimeError);
                 }
-                "seed" => {
-                    if self.runner_seeds.get(&EIP7702) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_seeds.remove(&EIP7702);
+                self.runner_seeds.remove(name);
+            }
+            "workers" => {
+                if !self.runner_workers.contains_key(name) {
+                    self.print(&format!("{} runner workers is already reset", name));
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
-            },
-            "legacy" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&Legacy) == None
-                        && self.runner_sks.get(&Legacy) == None
-                        && self.runner_seeds.get(&Legacy) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_rpcs.remove(&Legacy);
-                    self.runner_sks.remove(&Legacy);
-                    self.runner_seeds.remove(&Legacy);
+                self.runner_workers.remove(name);
+            }
+            "network" => {
+                self.print("network can only be reset globally");
+                return Err(AppStatus::RuntimeError);
+            }
+            _ => {
+                // Parameters not set for the instance fall back to the global
+                // ones, so resetting one means going back to the global value
+                let global = self.config.get(param).unwrap();
+                let Some(config) = self.runner_configs.get_mut(name) else {
+                    self.print(&format!("{} runner {} is already reset", name, param));
+                    return Err(AppStatus::RuntimeError);
+                };
+                config.set(param, &global).unwrap();
+                if config == &self.config {
+                    self.runner_configs.remove(name);
                 }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Helper function to parse the `key=value` options of the start command
+    // and store them as the config of the given runner instance
+    fn handle_start_options(&mut self, name: &str, options: &[&str]) -> Result<(), AppStatus> {
+        for option in options {
+            let Some((param, value)) = option.split_once('=') else {
+                self.print(&format!("invalid option: {}", option));
+                return Err(AppStatus::RuntimeError);
+            };
+
+            match param {
                 "rpc" => {
-                    if self.runner_rpcs.get(&Legacy) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
+                    if let Some(url) = Url::parse(value).ok().filter(is_supported_rpc) {
+                        self.runner_rpcs.insert(name.to_string(), url);
+                    } else {
+                        self.print(&format!("invalid rpc url: {}", value));
                         return Err(AppStatus::RuntimeError);
                     }
-                    self.runner_rpcs.remove(&Legacy);
                 }
                 "sk" => {
-                    if self.runner_sks.get(&Legacy) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
+                    if let Ok(sk) = parse_sk(value) {
+                        self.runner_sks.insert(name.to_string(), sk);
+                    } else {
+                        self.print(&format!("invalid sk: {}", value));
                         return Err(AppStatus::RuntimeError);
                     }
-                    self.runner_sks.remove(&Legacy);
                 }
                 "seed" => {
-                    if self.runner_seeds.get(&Legacy) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
+                    if let Ok(seed) = value.parse::<u64>() {
+                        self.runner_seeds.insert(name.to_string(), seed);
+                    } else {
+                        self.print(&format!("invalid seed: {}", value));
                         return Err(AppStatus::RuntimeError);
                     }
-                    self.runner_seeds.remove(&Legacy);
                 }
-                _ => unreachable!(),
-            },
-            "random" => match param {
-                "all" => {
-                    if self.runner_rpcs.get(&Random) == None
-                        && self.runner_sks.get(&Random) == None
-                        && self.runner_seeds.get(&Random) == None
-                    {
-                        self.print(&format!("{} runner is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
+                "workers" => match value.parse::<usize>() {
+                    Ok(workers) if workers > 0 => {
+                        self.runner_workers.insert(name.to_string(), workers);
                     }
-                    self.runner_rpcs.remove(&Random);
-                    self.runner_sks.remove(&Random);
-                    self.runner_seeds.remove(&Random);
-                }
-                "rpc" => {
-                    if self.runner_rpcs.get(&Random) == None {
-                        self.print(&format!("{} runner rpc is already reset", runner));
+                    _ => {
+                        self.print(&format!("invalid workers: {}", value));
                         return Err(AppStatus::RuntimeError);
                     }
-                    self.runner_rpcs.remove(&Random);
-                }
-                "sk" => {
-                    if self.runner_sks.get(&Random) == None {
-                        self.print(&format!("{} runner sk is already reset", runner));
+                },
+                _ if RunnerConfig::PARAMS.contains(&param) => {
+                    let mut config = self.runner_configs.get(name).unwrap_or(&self.config).clone();
+                    if let Err(e) = config.set(param, value) {
+                        self.print(&e);
                         return Err(AppStatus::RuntimeError);
                     }
-                    self.runner_sks.remove(&Random);
+                    self.runner_configs.insert(name.to_string(), config);
                 }
-                "seed" => {
-                    if self.runner_seeds.get(&Random) == None {
-                        self.print(&format!("{} runner seed is already reset", runner));
-                        return Err(AppStatus::RuntimeError);
-                    }
-                    self.runner_seeds.remove(&Random);
+                _ => {
+                    self.print(&format!("invalid parameter: {}", param));
+                    return Err(AppStatus::RuntimeError);
                 }
-                _ => unreachable!(),
-            },
-            _ => unreachable!(),
+            }
         }
 
         Ok(())
@@ -663,15 +466,12 @@ impl App {
 
                 if scope == "global" {
                     return self.handle_global_reset(param);
-                } else if self.is_valid_runner(scope) {
-                    return self.handle_runner_reset(scope, param);
                 } else {
-                    self.print(&format!("invalid scope: {}", scope));
-                    return Err(AppStatus::RuntimeError);
+                    return self.handle_runner_reset(scope, param);
                 }
             } else {
                 self.print(
-                    "invalid reset command format. Use: reset <global/RUNNER> <RPC/sk/seed/all>",
+                    "invalid reset command format. Use: reset <global/RUNNER> <RPC/sk/seed/workers/all>",
                 );
                 return Err(AppStatus::RuntimeError);
             }
@@ -684,86 +484,234 @@ impl App {
             }
 
             let runners: Vec<_> = self.active_runners.keys().cloned().collect();
-            for runner in runners {
+            if let Err(e) = self.stop_runners(&runners).await {
+                self.print(&format!("error stopping runner: {}", e));
+                return Err(AppStatus::RuntimeError);
+            }
+            self.print("all runners stopped");
+            return Ok(());
+        }
+
+        if command.starts_with("stop ") {
+            let parts: Vec<&str> = command.splitn(2, ' ').collect();
+            if parts.len() == 2 {
+                let runner = parts[1];
+                if !self.active_runners.contains_key(runner) {
+                    self.print(&format!("{} runner is not active", runner));
+                    return Err(AppStatus::RuntimeError);
+                }
+
                 if let Err(e) = self.stop_runner(runner).await {
                     self.print(&format!("error stopping runner: {}", e));
                     return Err(AppStatus::RuntimeError);
                 }
+                self.print(&format!("{} runner stopped", runner));
+                return Ok(());
+            } else {
+                self.print("invalid stop command format. Use: stop <RUNNER>");
+                return Err(AppStatus::RuntimeError);
+            }
+        }
+
+        if command.starts_with("pause ") {
+            let parts: Vec<&str> = command.splitn(2, ' ').collect();
+            if parts.len() == 2 {
+                let runner = parts[1];
+                if !self.active_runners.contains_key(runner) {
+                    self.print(&format!("{} runner is not active", runner));
+                    return Err(AppStatus::RuntimeError);
+                }
+
+                if let Err(e) = self.pause_runner(runner) {
+                    self.print(&format!("error pausing runner: {}", e));
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.print(&format!("{} runner paused", runner));
+                return Ok(());
+            } else {
+                self.print("invalid pause command format. Use: pause <RUNNER>");
+                return Err(AppStatus::RuntimeError);
             }
-            self.print("all runners stopped");
-            return Ok(());
         }
 
-        if command.starts_with("stop ") {
+        if command.starts_with("resume ") {
             let parts: Vec<&str> = command.splitn(2, ' ').collect();
             if parts.len() == 2 {
                 let runner = parts[1];
+                if !self.active_runners.contains_key(runner) {
+                    self.print(&format!("{} runner is not active", runner));
+                    return Err(AppStatus::RuntimeError);
+                }
+
+                if let Err(e) = self.resume_runner(runner) {
+                    self.print(&format!("error resuming runner: {}", e));
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.print(&format!("{} runner resumed", runner));
+                return Ok(());
+            } else {
+                self.print("invalid resume command format. Use: resume <RUNNER>");
+                return Err(AppStatus::RuntimeError);
+            }
+        }
+
+        if command.starts_with("checkpoint ") {
+            let parts: Vec<&str> = command.splitn(3, ' ').collect();
+            if parts.len() == 3 {
+                let runner = parts[1];
+                let file = parts[2];
+                if !self.active_runners.contains_key(runner) {
+                    self.print(&format!("{} runner is not active", runner));
+                    return Err(AppStatus::RuntimeError);
+                }
+
+                if let Err(e) = self.checkpoint_runner(runner, PathBuf::from(file)).await {
+                    self.print(&format!("error checkpointing runner: {}", e));
+                    return Err(AppStatus::RuntimeError);
+                }
+                self.print(&format!("{} runner checkpoint written to {}", runner, file));
+                return Ok(());
+            } else {
+                self.print("invalid checkpoint command format. Use: checkpoint <RUNNER> <FILE>");
+                return Err(AppStatus::RuntimeError);
+            }
+        }
+
+        if command.starts_with("restore ") {
+            let parts: Vec<&str> = command.split_whitespace().collect();
+            if parts.len() == 3 || (parts.len() == 5 && parts[3] == "as") {
+                let runner = parts[1];
+                let file = parts[2];
+                let name = parts.get(4).copied().unwrap_or(runner);
                 if !self.is_valid_runner(runner) {
                     self.print(&format!("invalid runner: {}", runner));
                     return Err(AppStatus::RuntimeError);
                 }
 
-                if !self.active_runners.contains_key(&Runner::from_str(runner).unwrap()) {
-                    self.print(&format!("{} runner is not active", runner));
+                if !self.is_valid_name(name, runner) {
+                    self.print(&format!("invalid runner name: {}", name));
                     return Err(AppStatus::RuntimeError);
                 }
 
-                if let Err(e) = self.stop_runner(Runner::from_str(runner).unwrap()).await {
-                    self.print(&format!("error stopping runner: {}", e));
+                if self.active_runners.contains_key(name) {
+                    self.print(&format!("{} runner is already active", name));
                     return Err(AppStatus::RuntimeError);
                 }
-                self.print(&format!("{} runner stopped", runner));
+
+                match self
+                    .restore_runner(name, Runner::from_str(runner).unwrap(), PathBuf::from(file))
+                    .await
+                {
+                    Ok(Some(warning)) => {
+                        self.print(&format!("{} runner restored from {} ({})", name, file, warning))
+                    }
+                    Ok(None) => self.print(&format!("{} runner restored from {}", name, file)),
+                    Err(e) => {
+                        self.print(&format!("error restoring runner: {}", e));
+                        return Err(AppStatus::RuntimeError);
+                    }
+                }
                 return Ok(());
             } else {
-                self.print("invalid stop command format. Use: stop <RUNNER>");
+                self.print(
+                    "invalid restore command format. Use: restore <RUNNER> <FILE> [as <NAME>]",
+                );
                 return Err(AppStatus::RuntimeError);
             }
         }
 
         if command == "exit" {
             // Stop all runners before exiting
-            for runner in [
-                AL, Blob, EIP1559, EIP7702, Legacy, Random,
-            ] {
-                let _ = self.stop_runner(runner).await;
-            }
+            let runners: Vec<_> = self.active_runners.keys().cloned().collect();
+            let _ = self.stop_runners(&runners).await;
             return Err(AppStatus::Exit);
         }
 
         if command == "start" {
+            let mut last_warning = None;
+            let mut started = vec![];
+            let mut failed = vec![];
             for runner in [
-                AL, Blob, EIP1559, EIP7702, Legacy, Random,
+                AL, Blob, EIP1559, EIP7702, Engine, Envelope, Legacy, Random, Scenario,
             ] {
-                let _ = self.start_runner(runner).await;
+                // An unsafe target stops the whole start, together with the
+                // runners it already started, instead of being skipped as
+                // runners that are already active. Any other error only keeps
+                // that runner from starting, as the engine one does without a
+                // JWT secret
+                let name = runner.to_string();
+                match self.start_runner(&name, runner, vec![]).await {
+                    Ok(warning) => {
+                        started.push(name);
+                        last_warning = warning.or(last_warning);
+                    }
+                    Err(e @ Error::UnsafeTarget(_)) => {
+                        let _ = self.stop_runners(&started).await;
+                        self.print(&format!("error starting runners: {}", e));
+                        return Err(AppStatus::RuntimeError);
+                    }
+                    Err(Error::RunnerAlreadyRunning) => {}
+                    Err(e) => failed.push(format!("{} ({})", runner, e)),
+                }
+            }
+
+            // Only the last message is shown, so the failures go in the status
+            let status = if failed.is_empty() {
+                "all runners started".to_string()
+            } else {
+                format!("{} runners started, failed to start: {}", started.len(), failed.join(", "))
+            };
+            match last_warning {
+                Some(warning) => self.print(&format!("{} ({})", status, warning)),
+                None => self.print(&status),
             }
-            self.print("all runners started");
             return Ok(());
         }
 
         if command.starts_with("start ") {
-            let parts: Vec<&str> = command.splitn(2, ' ').collect();
-            if parts.len() == 2 {
-                let runner = parts[1];
-                if !self.is_valid_runner(runner) {
-                    self.print(&format!("invalid runner: {}", runner));
-                    return Err(AppStatus::RuntimeError);
-                }
+            let parts: Vec<&str> = command.split_whitespace().collect();
+            let runner = parts[1];
+            if !self.is_valid_runner(runner) {
+                self.print(&format!("invalid runner: {}", runner));
+                return Err(AppStatus::RuntimeError);
+            }
 
-                if self.active_runners.contains_key(&Runner::from_str(runner).unwrap()) {
-                    self.print(&format!("{} runner is already active", runner));
-                    return Err(AppStatus::RuntimeError);
+            // Instances are named after their runner type unless `as <NAME>` is given
+            let (name, options) = if parts.get(2) == Some(&"as") {
+                match parts.get(3) {
+                    Some(name) => (*name, &parts[4..]),
+                    None => {
+                        self.print(
+                            "invalid start command format. Use: start <RUNNER> [as <NAME>] [<PARAM>=<VALUE>...]",
+                        );
+                        return Err(AppStatus::RuntimeError);
+                    }
                 }
+            } else {
+                (runner, &parts[2..])
+            };
 
-                if let Err(e) = self.start_runner(Runner::from_str(runner).unwrap()).await {
+            if !self.is_valid_name(name, runner) {
+                self.print(&format!("invalid runner name: {}", name));
+                return Err(AppStatus::RuntimeError);
+            }
+
+            if self.active_runners.contains_key(name) {
+                self.print(&format!("{} runner is already active", name));
+                return Err(AppStatus::RuntimeError);
+            }
+
+            self.handle_start_options(name, options)?;
+
+            match self.start_runner(name, Runner::from_str(runner).unwrap(), vec![]).await {
+                Ok(Some(warning)) => self.print(&format!("{} runner started ({})", name, warning)),
+                Ok(None) => self.print(&format!("{} runner started", name)),
+                Err(e) => {
                     self.print(&format!("error starting runner: {}", e));
                     return Err(AppStatus::RuntimeError);
                 }
-                self.print(&format!("{} runner started", runner));
-                return Ok(());
-            } else {
-                self.print("invalid start command format. Use: start <RUNNER>");
-                return Err(AppStatus::RuntimeError);
             }
+            return Ok(());
         }
 
         self.print("invalid command");
@@ -17,9 +17,15 @@ use ratatui::{
         Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarState, Wrap,
     },
 };
-use runners::{Runner, Runner::*};
-use std::{collections::HashMap, io, time::Duration};
-use tokio::task::JoinHandle;
+use runners::{Runner, Runner::*, config::RunnerConfig, control::Control, network::Network};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    ops::Range,
+    time::Duration,
+};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
 pub mod errors;
 pub mod handler;
 pub mod manager;
@@ -46,6 +52,14 @@ pub struct App {
     // The maximum number of operations per mutation.
     max_operations_per_mutation: u64,
 
+    // The network profile the global settings were taken from, if any. It
+    // is unset when the global settings are reset.
+    network: Option<Network>,
+
+    // Whether runners can be started against public chains and remote
+    // hosts, as set with `--i-know-what-im-doing`.
+    allow_unsafe_targets: bool,
+
     // The output buffer. This is used to store the output of the
     // command that is being executed.
     output: String,
@@ -80,25 +94,62 @@ pub struct App {
     // keep the input area at a fixed height.
     input_height: u16,
 
-    // The handler for each runner. This is used to abort the
-    // runner when the user wants to stop the fuzzing process of
-    // either a specific runner or all runners.
-    handler: HashMap<Runner, JoinHandle<()>>,
-
-    // The active runners. This is used to know which runners are
-    // currently running and update the information in the UI
-    // accordingly.
-    active_runners: HashMap<Runner, bool>,
-
-    // The seeds for each runner. This is to have more granular control
-    // over the runners.
-    runner_seeds: HashMap<Runner, u64>,
-
-    // The private keys for each runner. The same as with `runner_seeds`.
-    runner_sks: HashMap<Runner, SigningKey>,
-
-    // The RPC URLs for each runner. The same as with `runner_seeds`.
-    runner_rpcs: HashMap<Runner, Url>,
+    // The handlers for the workers of each runner instance, keyed by
+    // the instance name. This is used to wait for the workers to exit
+    // when the user wants to stop the fuzzing process of either a
+    // specific runner or all runners, and to abort them if they take
+    // too long.
+    handler: HashMap<String, Vec<JoinHandle<()>>>,
+
+    // The cancellation token for each runner instance. Workers check it
+    // between iterations and exit gracefully once it is cancelled.
+    cancellation_tokens: HashMap<String, CancellationToken>,
+
+    // The command channels for the workers of each runner instance. This
+    // is used to pause, resume and checkpoint them without stopping them.
+    controls: HashMap<String, Vec<UnboundedSender<Control>>>,
+
+    // The paused runner instances. They are still active, but their loop
+    // is suspended in place until they are resumed.
+    paused_runners: HashSet<String>,
+
+    // The active runner instances and their type. This is used to know
+    // which runners are currently running and update the information in
+    // the UI accordingly. Instances are named after their type unless
+    // started with `start <RUNNER> as <NAME>`, so that many instances of
+    // the same type can run at the same time.
+    active_runners: HashMap<String, Runner>,
+
+    // The accounts of `STATIC_KEYS` each active runner instance spreads its
+    // transactions across, as a range of indexes. They do not overlap, so
+    // that instances do not race on the nonces of the same accounts.
+    sender_slices: HashMap<String, Range<usize>>,
+
+    // The seeds for each runner instance. This is to have more granular
+    // control over the runners.
+    runner_seeds: HashMap<String, u64>,
+
+    // The private keys for each runner instance. The same as with
+    // `runner_seeds`.
+    runner_sks: HashMap<String, SigningKey>,
+
+    // The RPC URLs for each runner instance. The same as with
+    // `runner_seeds`.
+    runner_rpcs: HashMap<String, Url>,
+
+    // The number of workers for each runner instance. Each worker is a
+    // runner of the instance type with its own seed, all of them sending
+    // in parallel. Defaults to one.
+    runner_workers: HashMap<String, usize>,
+
+    // The global request settings of the runners, like the number of
+    // requests in flight or the rate limit. Applies to every runner
+    // instance without its own settings in `runner_configs`.
+    config: RunnerConfig,
+
+    // The request settings for each runner instance. The same as with
+    // `runner_seeds`.
+    runner_configs: HashMap<String, RunnerConfig>,
 }
 
 impl App {
@@ -109,16 +160,33 @@ impl App {
     /// * `rpc_url` - The URL of the RPC endpoint.
     /// * `sk` - The private key of the account that will be sending the transactions.
     /// * `seed` - The seed to use for the runners.
-    pub fn new(rpc_url: Url, sk: SigningKey, seed: u64, max_operations_per_mutation: u64) -> Self {
+    /// * `max_operations_per_mutation` - The maximum number of operations per mutation.
+    /// * `network` - The network profile the global settings come from, if any.
+    /// * `allow_unsafe_targets` - Whether to fuzz public chains and remote hosts.
+    pub fn new(
+        rpc_url: Url,
+        sk: SigningKey,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        network: Option<Network>,
+        allow_unsafe_targets: bool,
+    ) -> Self {
         let mut list_state = ListState::default();
         list_state.select(Some(0));
 
+        let mut config = RunnerConfig::default();
+        if let Some(network) = &network {
+            network.apply(&mut config);
+        }
+
         App {
             running: false,
             seed,
             sk: sk.clone(),
             rpc_url: rpc_url.clone(),
             max_operations_per_mutation,
+            network,
+            allow_unsafe_targets,
             output: String::new(),
             command_history: Vec::new(),
             output_history: Vec::new(),
@@ -129,10 +197,17 @@ impl App {
             left_panel_width: 70,
             input_height: 3,
             handler: HashMap::new(),
+            cancellation_tokens: HashMap::new(),
+            controls: HashMap::new(),
+            paused_runners: HashSet::new(),
             active_runners: HashMap::new(),
+            sender_slices: HashMap::new(),
             runner_seeds: HashMap::new(),
             runner_sks: HashMap::new(),
             runner_rpcs: HashMap::new(),
+            runner_workers: HashMap::new(),
+            config,
+            runner_configs: HashMap::new(),
         }
     }
 
@@ -297,13 +372,22 @@ impl App {
 
     /// Updates the status of all runners by checking if they are running
     fn update_runners_status(&mut self) {
-        // Check if any runner is still running by checking their task handles
-        for (runner_type, handle) in &self.handler {
-            if !handle.is_finished() {
-                self.active_runners.insert(*runner_type, true);
-            } else {
-                self.active_runners.remove(runner_type);
-            }
+        // Check if any runner is still running by checking the task handles of
+        // its workers
+        let finished: Vec<String> = self
+            .handler
+            .iter()
+            .filter(|(_, handles)| handles.iter().all(|handle| handle.is_finished()))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in finished {
+            self.handler.remove(&name);
+            self.cancellation_tokens.remove(&name);
+            self.controls.remove(&name);
+            self.paused_runners.remove(&name);
+            self.active_runners.remove(&name);
+            self.sender_slices.remove(&name);
         }
 
         // Update the global running status based on whether any runner is active
@@ -360,6 +444,15 @@ impl App {
                 Span::styled("Seed: ", Style::default().fg(Color::Yellow)),
                 Span::styled(self.seed.to_string(), Style::default().fg(Color::Green)),
             ]),
+            Line::from(vec![
+                Span::styled("Network: ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    self.network.as_ref().map_or("custom".to_string(), |network| {
+                        format!("{} (chain id {})", network.name, network.chain_id)
+                    }),
+                    Style::default().fg(Color::Green),
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("RPC: ", Style::default().fg(Color::Yellow)),
                 Span::styled(self.rpc_url.to_string(), Style::default().fg(Color::Green)),
@@ -374,43 +467,56 @@ impl App {
         ];
 
         let mut runners = vec![
-            AL, Blob, EIP1559, EIP7702, Legacy, Random,
+            AL, Blob, EIP1559, EIP7702, Engine, Envelope, Legacy, Random, Scenario,
         ];
 
         // Add active runners information
+        let mut names: Vec<&String> = self.active_runners.keys().collect();
+        names.sort();
+
         let mut active_runners = Vec::new();
-        for (runner, active) in &self.active_runners {
-            if *active {
-                // Here, if there is no per-runner `seed` or `sk`, we use the `global` seed and
-                // `sk`.
-                let seed = self.runner_seeds.get(runner).unwrap_or(&self.seed);
-                let address =
-                    Address::from_private_key(self.runner_sks.get(runner).unwrap_or(&self.sk));
-                let rpc = self.runner_rpcs.get(runner).unwrap_or(&self.rpc_url);
-
-                active_runners.push(Line::from(vec![
-                    Span::styled(format!("{}: ", runner), Style::default().fg(Color::Yellow)),
-                    Span::styled(
-                        format!(
-                            "seed={}, signer={}, rpc={}, ops={}",
-                            seed, address, rpc, self.max_operations_per_mutation
-                        ),
-                        Style::default().fg(Color::Green),
+        for name in names {
+            let runner = self.active_runners[name];
+
+            // Here, if there is no per-runner `seed` or `sk`, we use the `global` seed and
+            // `sk`.
+            let seed = self.runner_seeds.get(name).unwrap_or(&self.seed);
+            let address = Address::from_private_key(self.runner_sks.get(name).unwrap_or(&self.sk));
+            let rpc = self.runner_rpcs.get(name).unwrap_or(&self.rpc_url);
+            let workers = self.runner_workers.get(name).unwrap_or(&1);
+            let config = self.runner_configs.get(name).unwrap_or(&self.config);
+            let label = if name == &runner.to_string() {
+                name.to_string()
+            } else {
+                format!("{} ({})", name, runner)
+            };
+            let label = if self.paused_runners.contains(name) {
+                format!("{} [paused]: ", label)
+            } else {
+                format!("{}: ", label)
+            };
+
+            active_runners.push(Line::from(vec![
+                Span::styled(label, Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!(
+                        "seed={}, signer={}, rpc={}, ops={}, workers={}, {}",
+                        seed, address, rpc, self.max_operations_per_mutation, workers, config
                     ),
-                ]));
+                    Style::default().fg(Color::Green),
+                ),
+            ]));
 
-                runners.remove(runners.iter().position(|r| r == runner).unwrap());
+            // Runner types can be started again under a different name, so they
+            // are only unavailable while their default instance is active
+            if let Some(position) = runners.iter().position(|r| &r.to_string() == name) {
+                runners.remove(position);
             }
         }
 
         let mut available_runners = String::new();
         for runner in runners {
-            let line = Line::from(vec![
-                Span::styled(format!("{}", runner), Style::default().fg(Color::Yellow)),
-            ]);
-            if !active_runners.contains(&line) {
-                available_runners.push_str(&format!("{} ", runner));
-            }
+            available_runners.push_str(&format!("{} ", runner));
         }
 
         // Build all the lines to be displayed in the stats panel.
@@ -1,162 +1,557 @@
 use crate::App;
-use common::errors::Error;
+use alloy::{
+    primitives::{Address, U256, utils::format_ether},
+    providers::Provider,
+    signers::k256::ecdsa::SigningKey,
+    transports::http::reqwest::Url,
+};
+use common::{
+    connect,
+    constants::{LARGE_BALANCE, PUBLIC_CHAINS, RUNNER_STOP_TIMEOUT, STATIC_KEYS},
+    errors::Error,
+    is_local_rpc, parse_sk,
+    types::Backend,
+};
 use runners::{
-    Runner, Runner::*, al::ALTransactionRunner, blob::BlobTransactionRunner,
-    eip1559::Eip1559TransactionRunner, eip7702::Eip7702TransactionRunner,
-    legacy::LegacyTransactionRunner, random::RandomTransactionRunner,
+    Runner,
+    Runner::*,
+    al::ALTransactionRunner,
+    blob::BlobTransactionRunner,
+    checkpoint::Checkpoint,
+    config::RunnerConfig,
+    control::{Control, Controller, Worker},
+    eip1559::Eip1559TransactionRunner,
+    eip7702::Eip7702TransactionRunner,
+    engine::EngineRunner,
+    envelope::EnvelopeRunner,
+    jwt::JwtSecret,
+    legacy::LegacyTransactionRunner,
+    random::RandomTransactionRunner,
+    scenario::ScenarioRunner,
+    senders::SenderPool,
 };
+use std::{iter::once, ops::Range, path::PathBuf, time::Duration};
+use tokio::{
+    sync::oneshot,
+    task::JoinHandle,
+    time::{Instant, timeout_at},
+};
+use tokio_util::sync::CancellationToken;
 
 impl App {
-    /// Starts a runner given its type. This function spawns a thread and
-    /// stores its `handle` in the `handler` map. That way we can stop it
-    /// later on by calling `abort` on the `handle`.
+    /// Starts a runner instance given its name and type. This function spawns
+    /// a thread per worker of the instance and stores their `handle`s in the
+    /// `handler` map, together with the cancellation token the workers check
+    /// between iterations. That way we can ask them to stop later on, and
+    /// `abort` them if they do not.
     ///
     /// # Arguments
     ///
+    /// * `name` - The name of the runner instance.
     /// * `runner_type` - The type of the runner to start.
-    pub async fn start_runner(&mut self, runner_type: Runner) -> Result<(), Error> {
-        if *self.active_runners.get(&runner_type).unwrap_or(&false) {
+    /// * `checkpoints` - The checkpoints to restore the workers from, if any.
+    ///
+    /// # Returns
+    ///
+    /// A warning about the signer of the instance, if any.
+    pub async fn start_runner(
+        &mut self,
+        name: &str,
+        runner_type: Runner,
+        checkpoints: Vec<Checkpoint>,
+    ) -> Result<Option<String>, Error> {
+        if self.active_runners.contains_key(name) {
             return Err(Error::RunnerAlreadyRunning);
         }
 
-        let sk = self.runner_sks.get(&runner_type).unwrap_or(&self.sk).clone();
-        let seed = *self.runner_seeds.get(&runner_type).unwrap_or(&self.seed);
-        let rpc = self.runner_rpcs.get(&runner_type).unwrap_or(&self.rpc_url).clone();
+        let sk = self.runner_sks.get(name).unwrap_or(&self.sk).clone();
+        let seed = *self.runner_seeds.get(name).unwrap_or(&self.seed);
+        let rpc = self.runner_rpcs.get(name).unwrap_or(&self.rpc_url).clone();
+        let config = self.runner_configs.get(name).unwrap_or(&self.config).clone();
+        let token = CancellationToken::new();
 
-        match runner_type {
-            AL => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner =
-                            ALTransactionRunner::new(rpc, sk, seed, max_operations_per_mutation)
-                                .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
-            }
-            Blob => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner =
-                            BlobTransactionRunner::new(rpc, sk, seed, max_operations_per_mutation)
-                                .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
+        // Check that the node is reachable through the transport of the URL
+        // before spawning the workers, which expect a working connection
+        let provider = connect(&rpc, &sk).await?;
+
+        // Malformed transactions are signed with keys that may hold real
+        // funds, so public chains and remote hosts are refused by default
+        let warning = self.check_target(&provider, &rpc, &sk).await?;
+
+        // The envelope runner writes raw HTTP requests over TCP, so it can only
+        // be pointed at plain HTTP endpoints
+        if runner_type == Envelope && rpc.scheme() != "http" {
+            return Err(Error::InvalidRpcUrl(rpc.to_string()));
+        }
+
+        // The engine runner authenticates with the JWT secret of the node
+        if runner_type == Engine {
+            JwtSecret::from_file(&config.jwt_secret).map_err(Error::InvalidJwtSecret)?;
+
+            if !self.allow_unsafe_targets && !is_local_rpc(&config.engine_url) {
+                return Err(Error::UnsafeTarget(format!(
+                    "remote host {}",
+                    config.engine_url.host_str().unwrap_or_default()
+                )));
             }
-            EIP1559 => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner = Eip1559TransactionRunner::new(
-                            rpc,
-                            sk,
-                            seed,
-                            max_operations_per_mutation,
-                        )
-                        .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
+        }
+
+        // A restored instance has as many workers as checkpoints were taken
+        let workers = if checkpoints.is_empty() {
+            *self.runner_workers.get(name).unwrap_or(&1)
+        } else {
+            checkpoints.len()
+        };
+
+        // Each worker gets its own accounts for its sender pool, out of a
+        // slice of `STATIC_KEYS` no other running instance uses
+        let size = SenderPool::size(runner_type, &config);
+        let slice = self.sender_slice(size * workers)?;
+
+        // The accounts of the sender pools are funded once for the whole
+        // instance, so that its workers do not race on the nonce of the faucet
+        // and read the nonces that follow the funding
+        SenderPool::fund(&provider, slice.start, slice.len()).await;
+
+        let mut checkpoints = checkpoints.into_iter();
+        let mut handles = vec![];
+        let mut controls = vec![];
+        for worker in 0..workers {
+            // Each worker gets its own seed and logger, so that they do not
+            // send the same transactions nor write to the same files
+            let worker_name =
+                if workers == 1 { name.to_string() } else { format!("{}-{}", name, worker) };
+            let worker_seed = seed.wrapping_add(worker as u64);
+            let worker_config =
+                RunnerConfig { sender_offset: slice.start + worker * size, ..config.clone() };
+
+            let (controller, control) = Controller::new(token.clone());
+            let handle = self.spawn_runner(
+                runner_type,
+                worker_name,
+                rpc.clone(),
+                sk.clone(),
+                worker_seed,
+                worker_config,
+                checkpoints.next(),
+                controller,
+            );
+
+            handles.push(handle);
+            controls.push(control);
+        }
+
+        self.handler.insert(name.to_string(), handles);
+        self.cancellation_tokens.insert(name.to_string(), token);
+        self.controls.insert(name.to_string(), controls);
+        self.active_runners.insert(name.to_string(), runner_type);
+        self.sender_slices.insert(name.to_string(), slice);
+        self.running = true;
+
+        Ok(warning)
+    }
+
+    // Helper function to find the first `size` accounts of `STATIC_KEYS` that
+    // no running instance uses, so that their nonces are only tracked by the
+    // instance being started
+    fn sender_slice(&self, size: usize) -> Result<Range<usize>, Error> {
+        let taken: Vec<&Range<usize>> = self.sender_slices.values().collect();
+
+        // A free slice either starts at the first key or right after a taken one
+        once(0)
+            .chain(taken.iter().map(|slice| slice.end))
+            .map(|start| start..start + size)
+            .filter(|slice| slice.end <= STATIC_KEYS.len())
+            .filter(|slice| taken.iter().all(|t| slice.end <= t.start || t.end <= slice.start))
+            .min_by_key(|slice| slice.start)
+            .ok_or(Error::NotEnoughSenders(size))
+    }
+
+    // Helper function to check that the node of a runner instance is a local
+    // development one. It reads the chain id of the node and refuses public
+    // chains and remote hosts unless `--i-know-what-im-doing` was given, and
+    // warns when the signer holds a large balance, unless it is the known
+    // development key of the profile on its own network, as development
+    // networks fund their keys generously.
+    async fn check_target(
+        &self,
+        provider: &Backend,
+        rpc: &Url,
+        sk: &SigningKey,
+    ) -> Result<Option<String>, Error> {
+        let chain_id =
+            provider.get_chain_id().await.map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+
+        if !self.allow_unsafe_targets {
+            if let Some((_, chain)) = PUBLIC_CHAINS.iter().find(|(id, _)| *id == chain_id) {
+                return Err(Error::UnsafeTarget(format!("{} (chain id {})", chain, chain_id)));
             }
-            EIP7702 => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner = Eip7702TransactionRunner::new(
-                            rpc,
-                            sk,
-                            seed,
-                            max_operations_per_mutation,
-                        )
-                        .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
+
+            if !is_local_rpc(rpc) {
+                return Err(Error::UnsafeTarget(format!(
+                    "remote host {}",
+                    rpc.host_str().unwrap_or_default()
+                )));
             }
-            Legacy => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner = LegacyTransactionRunner::new(
-                            rpc,
-                            sk,
-                            seed,
-                            max_operations_per_mutation,
-                        )
-                        .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
+        }
+
+        // A node answering with the chain id of the profile may still be any
+        // node, so only the key of the profile is trusted to hold test funds
+        let address = Address::from_private_key(sk);
+        let dev_key = self.network.as_ref().is_some_and(|network| {
+            network.chain_id == chain_id
+                && parse_sk(&network.sk).is_ok_and(|dev| Address::from_private_key(&dev) == address)
+        });
+        if dev_key {
+            return Ok(None);
+        }
+
+        let balance = provider.get_balance(address).await.unwrap_or_default();
+        Ok((balance > U256::from(LARGE_BALANCE)).then(|| {
+            format!(
+                "warning: {} holds {} ether on chain id {}",
+                address,
+                format_ether(balance),
+                chain_id
+            )
+        }))
+    }
+
+    // Helper function to spawn a single worker of a runner instance
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_runner(
+        &self,
+        runner_type: Runner,
+        name: String,
+        rpc: Url,
+        sk: SigningKey,
+        seed: u64,
+        config: RunnerConfig,
+        checkpoint: Option<Checkpoint>,
+        controller: Controller,
+    ) -> JoinHandle<()> {
+        let max_operations_per_mutation = self.max_operations_per_mutation;
+
+        // Setting up a worker waits on the node, for instance for the fixture
+        // contracts to be mined, so it is cut short if the instance is stopped
+        // meanwhile
+        match runner_type {
+            AL => tokio::spawn(async move {
+                let setup = ALTransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Blob => tokio::spawn(async move {
+                let setup = BlobTransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            EIP1559 => tokio::spawn(async move {
+                let setup = Eip1559TransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            EIP7702 => tokio::spawn(async move {
+                let setup = Eip7702TransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Engine => tokio::spawn(async move {
+                let setup =
+                    EngineRunner::new(&name, rpc, sk, seed, max_operations_per_mutation, config);
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Envelope => tokio::spawn(async move {
+                let setup =
+                    EnvelopeRunner::new(&name, rpc, sk, seed, max_operations_per_mutation, config);
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Legacy => tokio::spawn(async move {
+                let setup = LegacyTransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Random => tokio::spawn(async move {
+                let setup = RandomTransactionRunner::new(
+                    &name,
+                    rpc,
+                    sk,
+                    seed,
+                    max_operations_per_mutation,
+                    config,
+                );
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+            Scenario => tokio::spawn(async move {
+                let setup =
+                    ScenarioRunner::new(&name, rpc, sk, seed, max_operations_per_mutation, config);
+                let Some(mut runner) = controller.until_stopped(setup).await else {
+                    return;
+                };
+                if let Some(checkpoint) = checkpoint {
+                    runner.restore(checkpoint);
+                }
+                runner.run(controller).await;
+            }),
+        }
+    }
+
+    /// Stops a runner instance given its name. The workers are signaled through
+    /// their cancellation token, so that they can flush their logger and corpus
+    /// and write the session summary before exiting. Workers that do not exit
+    /// within `RUNNER_STOP_TIMEOUT` seconds are aborted.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the runner instance to stop.
+    pub async fn stop_runner(&mut self, name: &str) -> Result<(), Error> {
+        self.stop_runners(&[name.to_string()]).await
+    }
+
+    /// Stops several runner instances at once. All of them are signaled
+    /// before waiting for any, and they share the same `RUNNER_STOP_TIMEOUT`
+    /// seconds to exit, so stopping many instances takes no longer than
+    /// stopping one.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the runner instances to stop.
+    pub async fn stop_runners(&mut self, names: &[String]) -> Result<(), Error> {
+        if names.iter().any(|name| !self.active_runners.contains_key(name)) {
+            return Err(Error::RunnerAlreadyStopped);
+        }
+
+        // Signal the workers to stop after their current iteration
+        for name in names {
+            if let Some(token) = self.cancellation_tokens.remove(name) {
+                token.cancel();
             }
-            Random => {
-                let handle = tokio::spawn({
-                    let rpc = rpc.clone();
-                    let sk = sk.clone();
-                    let max_operations_per_mutation = self.max_operations_per_mutation;
-                    async move {
-                        let mut runner = RandomTransactionRunner::new(
-                            rpc,
-                            sk,
-                            seed,
-                            max_operations_per_mutation,
-                        )
-                        .await;
-                        runner.run().await;
-                    }
-                });
-
-                self.handler.insert(runner_type, handle);
+        }
+
+        // Wait for the workers to exit on their own, and abort them as a fallback
+        let deadline = Instant::now() + Duration::from_secs(RUNNER_STOP_TIMEOUT);
+        for name in names {
+            for mut handle in self.handler.remove(name).unwrap_or_default() {
+                if timeout_at(deadline, &mut handle).await.is_err() {
+                    handle.abort();
+                }
             }
+
+            // Remove the runner from the active runners map
+            self.controls.remove(name);
+            self.paused_runners.remove(name);
+            self.active_runners.remove(name);
+            self.sender_slices.remove(name);
         }
 
-        self.active_runners.insert(runner_type, true);
-        if self.active_runners.values().any(|&active| active) {
-            self.running = true;
+        // If all runners are stopped, set the running flag to false
+        if self.active_runners.is_empty() {
+            self.running = false;
         }
 
         Ok(())
     }
 
-    pub async fn stop_runner(&mut self, runner_type: Runner) -> Result<(), Error> {
-        if !self.active_runners.get(&runner_type).unwrap_or(&false) {
+    /// Pauses a runner instance given its name. The workers suspend their loop
+    /// in place after their current iteration, keeping all of their state.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the runner instance to pause.
+    pub fn pause_runner(&mut self, name: &str) -> Result<(), Error> {
+        if !self.active_runners.contains_key(name) {
             return Err(Error::RunnerAlreadyStopped);
         }
 
-        // Get the handle for this runner and abort it
-        if let Some(handle) = self.handler.remove(&runner_type) {
-            // Cancel the handle to signal the runner to stop
-            handle.abort();
+        if self.paused_runners.contains(name) {
+            return Err(Error::RunnerAlreadyPaused);
         }
 
-        // Remove the runner from the active runners map
-        self.active_runners.remove(&runner_type);
+        self.send_control(name, || Control::Pause)?;
+        self.paused_runners.insert(name.to_string());
 
-        // If all runners are stopped, set the running flag to false
-        if self.active_runners.values().all(|&active| !active) {
-            self.running = false;
+        Ok(())
+    }
+
+    /// Resumes a paused runner instance given its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the runner instance to resume.
+    pub fn resume_runner(&mut self, name: &str) -> Result<(), Error> {
+        if !self.paused_runners.contains(name) {
+            return Err(Error::RunnerNotPaused);
+        }
+
+        self.send_control(name, || Control::Resume)?;
+        self.paused_runners.remove(name);
+
+        Ok(())
+    }
+
+    /// Writes the state of every worker of a runner instance to disk, so that
+    /// it can be restored later on with `restore_runner`. The workers take
+    /// their checkpoint between iterations, so this waits up to
+    /// `RUNNER_STOP_TIMEOUT` seconds for them to do so.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the runner instance to checkpoint.
+    /// * `path` - The file to write the checkpoint to.
+    pub async fn checkpoint_runner(&mut self, name: &str, path: PathBuf) -> Result<(), Error> {
+        if !self.active_runners.contains_key(name) {
+            return Err(Error::RunnerAlreadyStopped);
+        }
+
+        let mut responses = vec![];
+        self.send_control(name, || {
+            let (reply, response) = oneshot::channel();
+            responses.push(response);
+            Control::Checkpoint(reply)
+        })?;
+
+        let deadline = Instant::now() + Duration::from_secs(RUNNER_STOP_TIMEOUT);
+        let mut checkpoints = vec![];
+        for response in responses {
+            match timeout_at(deadline, response).await {
+                Ok(Ok(checkpoint)) => checkpoints.push(checkpoint),
+                Ok(Err(_)) => return Err(Error::CheckpointFailed("runner exited".to_string())),
+                Err(_) => {
+                    return Err(Error::CheckpointFailed("runner did not respond".to_string()));
+                }
+            }
+        }
+
+        Checkpoint::save(&checkpoints, &path)
+            .map_err(|err| Error::CheckpointFailed(err.to_string()))
+    }
+
+    /// Starts a runner instance from a checkpoint previously written by
+    /// `checkpoint_runner`, so that every worker continues from where it
+    /// stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the runner instance to start.
+    /// * `runner_type` - The type of the runner to restore.
+    /// * `path` - The file to read the checkpoint from.
+    ///
+    /// # Returns
+    ///
+    /// A warning about the signer of the instance, if any.
+    pub async fn restore_runner(
+        &mut self,
+        name: &str,
+        runner_type: Runner,
+        path: PathBuf,
+    ) -> Result<Option<String>, Error> {
+        let checkpoints =
+            Checkpoint::load(&path).map_err(|err| Error::InvalidCheckpoint(err.to_string()))?;
+
+        if checkpoints.is_empty() {
+            return Err(Error::InvalidCheckpoint("checkpoint is empty".to_string()));
+        }
+
+        if let Some(checkpoint) = checkpoints.iter().find(|c| c.runner != runner_type.to_string()) {
+            return Err(Error::InvalidCheckpoint(format!(
+                "checkpoint belongs to the {} runner",
+                checkpoint.runner
+            )));
+        }
+
+        self.start_runner(name, runner_type, checkpoints).await
+    }
+
+    // Helper function to send a command to every worker of a runner instance.
+    // Workers that already exited, for instance after a crash, are skipped,
+    // so that the others still get the command; it only fails if none did.
+    fn send_control(
+        &mut self,
+        name: &str,
+        mut control: impl FnMut() -> Control,
+    ) -> Result<(), Error> {
+        let controls = self.controls.get(name).ok_or(Error::RunnerAlreadyStopped)?;
+        let delivered = controls.iter().filter(|sender| sender.send(control()).is_ok()).count();
+        if delivered == 0 {
+            return Err(Error::RunnerAlreadyStopped);
         }
 
         Ok(())
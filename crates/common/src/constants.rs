@@ -107,20 +107,81 @@ pub const ADDR: &str = "0xb02A2EdA1b317FBd16760128836B0Ac59B560e9D";
 
 pub const SK2: &str = "0x8c04e41e317a7cf0cf4c2f7431d0a890a950f352df41ff6d053698df61a73bba";
 
+pub const ANVIL_SK: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"; // first account of anvil and reth --dev
+
+pub const KURTOSIS_SK: &str = "0xbcdf20249abf0ed6d944c0288fad489e33f66b3960d9e6229c1cd214ed3bbe31"; // first prefunded account of the ethereum-package
+
+pub const PUBLIC_CHAINS: [(u64, &str); 12] = [
+    (1, "mainnet"),
+    (11155111, "sepolia"),
+    (17000, "holesky"),
+    (560048, "hoodi"),
+    (10, "optimism"),
+    (8453, "base"),
+    (42161, "arbitrum one"),
+    (42170, "arbitrum nova"),
+    (324, "zksync era"),
+    (137, "polygon"),
+    (59144, "linea"),
+    (534352, "scroll"),
+];
+
+pub const LARGE_BALANCE: u128 = 1_000_000_000_000_000_000; // 1 ether, above which a signer outside its network is warned about
+
 pub const MAX_GAS_LIMIT: u64 = 30000000;
 
+pub const MAX_TRANSACTION_GAS_LIMIT: u64 = 16777216; // 2^24, the per-transaction cap of EIP-7825
+
 pub const MAX_INPUT_LENGTH: usize = 1024;
 
+pub const MAX_PROGRAM_SNIPPETS: usize = 32; // per body of a generated EVM program
+
+pub const MAX_PROGRAM_DEPTH: usize = 3; // of jumps, loops and creations nested in a program
+
+pub const MAX_PRECOMPILE_PAIRS: usize = 4; // of points, or points and scalars, per precompile call
+
 pub const MAX_ACCESS_LIST_LENGTH: usize = 1024;
 
 pub const MAX_ACCESSED_KEYS_LENGTH: usize = 1024;
 
-pub const MAX_TRANSACTION_TYPE: u8 = 5; // one more for invalid testing
-
 pub const MAX_BLOB_VERSIONED_HASHES_LENGTH: usize = 1024;
 
 pub const MAX_BLOB_SIDECAR_LENGTH: usize = 1024;
 
 pub const MAX_AUTHORIZATION_LIST_LENGTH: usize = 1024;
 
+pub const MIN_SENDER_BALANCE: u128 = 1_000_000_000_000_000_000; // 1 ether, below which pool accounts are funded
+
+pub const SENDER_FUNDING: u128 = 10_000_000_000_000_000_000; // 10 ether sent from the faucet to each pool account
+
+pub const SENDER_FUNDING_TIMEOUT: u64 = 12; // seconds to wait for the funding of the pool accounts to be mined
+
+pub const MAX_NONCE_GAP: u64 = 16; // how far ahead of the pending nonce gapped transactions go
+
+pub const MAX_PENDING_NONCES: u64 = 64; // how far ahead of the node the local nonce can be before it is reset
+
+pub const SCENARIO_SENDERS: usize = 16; // senders of the scenario runner when `senders` is below two
+
+pub const POOL_FILL_TRANSACTIONS: usize = 6144; // above the default global slots and queue of geth
+
+pub const REPLACEMENT_STORM_LENGTH: usize = 64; // transactions sent with the same nonce in a storm
+
+pub const GAP_CHAIN_LENGTH: u64 = 64; // queued transactions per sender behind a nonce gap
+
+pub const BLOB_SCENARIO_TRANSACTIONS: usize = 32; // before replacements, as every blob is generated with its proof
+
+pub const MAX_SIGNED_AUTHORIZATIONS: usize = 8; // authorizations signed by STATIC_KEYS per transaction
+
 pub const MAX_TRANSACTION_LENGTH: usize = 1024 * 100; // revisit
+
+pub const FIXTURE_RECEIPT_TIMEOUT: u64 = 12; // seconds to wait for the fixture contracts to be mined
+
+pub const RUNNER_STOP_TIMEOUT: u64 = 10; // seconds before aborting a runner that did not stop
+
+pub const MAX_ENVELOPE_BODY_LENGTH: usize = 1024 * 1024 * 16; // giant bodies sent by the envelope runner
+
+pub const ENVELOPE_RESPONSE_TIMEOUT: u64 = 5; // seconds to wait for the node to answer a raw request
+
+pub const MAX_PAYLOAD_TRANSACTIONS: usize = 64;
+
+pub const MAX_WITHDRAWALS: usize = 16; // per payload, as in the consensus specs
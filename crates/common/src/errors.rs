@@ -7,6 +7,14 @@ pub enum Error {
     InvalidKey,
     RunnerAlreadyRunning,
     RunnerAlreadyStopped,
+    RunnerAlreadyPaused,
+    RunnerNotPaused,
+    InvalidCheckpoint(String),
+    CheckpointFailed(String),
+    ConnectionFailed(String),
+    InvalidJwtSecret(String),
+    UnsafeTarget(String),
+    NotEnoughSenders(usize),
 }
 
 impl Display for Error {
@@ -17,6 +25,22 @@ impl Display for Error {
             Error::InvalidKey => write!(f, "invalid key"),
             Error::RunnerAlreadyRunning => write!(f, "runner already running"),
             Error::RunnerAlreadyStopped => write!(f, "runner already stopped"),
+            Error::RunnerAlreadyPaused => write!(f, "runner already paused"),
+            Error::RunnerNotPaused => write!(f, "runner not paused"),
+            Error::InvalidCheckpoint(err) => write!(f, "invalid checkpoint: {}", err),
+            Error::CheckpointFailed(err) => write!(f, "checkpoint failed: {}", err),
+            Error::ConnectionFailed(err) => write!(f, "connection failed: {}", err),
+            Error::InvalidJwtSecret(err) => write!(f, "invalid jwt secret: {}", err),
+            Error::UnsafeTarget(target) => {
+                write!(
+                    f,
+                    "refusing to fuzz {}, pass --i-know-what-im-doing to do it anyway",
+                    target
+                )
+            }
+            Error::NotEnoughSenders(size) => {
+                write!(f, "not enough free accounts in STATIC_KEYS for {} senders", size)
+            }
         }
     }
 }
@@ -1,5 +1,11 @@
-use crate::errors::Error;
-use alloy::{hex::decode, signers::k256::ecdsa::SigningKey};
+use crate::{errors::Error, types::Backend};
+use alloy::{
+    hex::decode,
+    providers::{IpcConnect, ProviderBuilder, WsConnect},
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
+    transports::http::reqwest::Url,
+};
+use std::{net::IpAddr, path::PathBuf};
 
 pub mod constants;
 pub mod errors;
@@ -10,3 +16,52 @@ pub fn parse_sk(sk: &str) -> Result<SigningKey, Error> {
         .map_err(|_| Error::InvalidKey)?;
     Ok(sk)
 }
+
+/// Returns whether the scheme of the given RPC URL is one of the supported
+/// transports, that is, `http`, `https`, `ws`, `wss` or `ipc`
+pub fn is_supported_rpc(rpc_url: &Url) -> bool {
+    [
+        "http", "https", "ws", "wss", "ipc",
+    ]
+    .contains(&rpc_url.scheme())
+}
+
+/// Returns whether the node behind the given RPC URL runs on this machine or
+/// in a private network, that is, over IPC, on a loopback, private or
+/// link-local address, or on `localhost` or a single label host, as the ones
+/// of docker and Kurtosis services
+pub fn is_local_rpc(rpc_url: &Url) -> bool {
+    if rpc_url.scheme() == "ipc" {
+        return true;
+    }
+
+    let Some(host) = rpc_url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback() || ip.is_unique_local(),
+        Err(_) => host == "localhost" || host.ends_with(".localhost") || !host.contains('.'),
+    }
+}
+
+/// Connects to the node behind `rpc_url` with a provider signing with `sk`. The
+/// transport is chosen by the scheme of the URL: `http://` and `https://` for
+/// HTTP, `ws://` and `wss://` for WebSocket and `ipc:///path/to/node.ipc` for
+/// a Unix socket.
+pub async fn connect(rpc_url: &Url, sk: &SigningKey) -> Result<Backend, Error> {
+    let builder = ProviderBuilder::new().wallet::<PrivateKeySigner>(sk.clone().into());
+
+    match rpc_url.scheme() {
+        "http" | "https" => Ok(builder.connect_http(rpc_url.clone())),
+        "ws" | "wss" => builder
+            .connect_ws(WsConnect::new(rpc_url.as_str()))
+            .await
+            .map_err(|e| Error::ConnectionFailed(e.to_string())),
+        "ipc" => builder
+            .connect_ipc(IpcConnect::new(PathBuf::from(rpc_url.path())))
+            .await
+            .map_err(|e| Error::ConnectionFailed(e.to_string())),
+        _ => Err(Error::InvalidRpcUrl(rpc_url.to_string())),
+    }
+}
@@ -43,6 +43,16 @@ impl Mutator {
         }
     }
 
+    /// Returns the maximum number of operations per mutation
+    pub fn max_operations_per_mutation(&self) -> u64 {
+        self.max_operations_per_mutation
+    }
+
+    /// Returns the seed for the random number generator
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     /// Mutate the input
     pub fn mutate(&self, input: &mut [u8]) {
         let mut rng = StdRng::seed_from_u64(self.seed);
@@ -0,0 +1,110 @@
+use alloy::{
+    hex,
+    rpc::json_rpc::{Id, Request, RequestPacket},
+    transports::{BoxTransport, TransportResult},
+};
+use rand::{Rng, rngs::StdRng, seq::SliceRandom};
+use serde_json::{Value, json};
+use std::future::poll_fn;
+use tower::Service;
+
+/// Methods mixed in between the raw transactions of a batch, so that the node
+/// has to handle different kinds of calls in the same packet
+const MIXED_METHODS: [&str; 4] = [
+    "eth_chainId",
+    "eth_blockNumber",
+    "eth_gasPrice",
+    "txpool_status",
+];
+
+/// Builds a JSON-RPC batch of `eth_sendRawTransaction` calls out of the given
+/// raw transactions. Besides well-formed entries, the batch randomly contains
+/// duplicated entries, duplicated ids, ids of odd types, params of the wrong
+/// type and calls to other methods, and the order of the entries is shuffled.
+///
+/// # Arguments
+///
+/// * `payloads` - The raw transactions to send in the batch.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The batch, ready to be sent through the transport of the provider.
+pub fn build(payloads: &[Vec<u8>], random: &mut StdRng) -> RequestPacket {
+    let mut packet = RequestPacket::with_capacity(payloads.len());
+    let mut ids: Vec<Id> = vec![];
+
+    for payload in payloads {
+        let raw = format!("0x{}", hex::encode(payload));
+
+        // 5% chance to mix in a call to another method
+        if random.random_bool(0.05) {
+            let method = MIXED_METHODS[random.random_range(0..MIXED_METHODS.len())];
+            push(&mut packet, method, Id::Number(ids.len() as u64), json!([]));
+        }
+
+        let id = match random.random_range(0..20) {
+            // Same id as a previous entry
+            0 if !ids.is_empty() => ids[random.random_range(0..ids.len())].clone(),
+            1 => Id::String(raw.clone()),
+            2 => Id::Number(u64::MAX),
+            3 => Id::None,
+            _ => Id::Number(ids.len() as u64),
+        };
+
+        let params = match random.random_range(0..20) {
+            // The bytes as an array of numbers instead of a hex string
+            0 => json!([payload]),
+            1 => json!([]),
+            2 => json!([
+                raw, raw
+            ]),
+            // A hex string with an odd number of digits
+            3 => json!([&raw[..raw.len() - 1]]),
+            4 => json!({ "data": raw }),
+            5 => json!(raw),
+            _ => json!([raw]),
+        };
+
+        // 5% chance to send the very same entry twice
+        if random.random_bool(0.05) {
+            push(&mut packet, "eth_sendRawTransaction", id.clone(), params.clone());
+        }
+
+        push(&mut packet, "eth_sendRawTransaction", id.clone(), params);
+        ids.push(id);
+    }
+
+    // 50% chance to shuffle the entries, so that ids are not in order
+    if random.random_bool(0.5) {
+        packet.requests_mut().shuffle(random);
+    }
+
+    packet
+}
+
+/// Sends a batch through the given transport. Errors returned by the node for
+/// single entries are not reported, only failures of the whole request.
+pub async fn send(mut transport: BoxTransport, packet: RequestPacket) -> TransportResult<()> {
+    poll_fn(|cx| transport.poll_ready(cx)).await?;
+    transport.call(packet).await.map(|_| ())
+}
+
+// Helper function to add an entry to the batch
+fn push(packet: &mut RequestPacket, method: &'static str, id: Id, params: Value) {
+    if let Ok(request) = Request::new(method, id, params).serialize() {
+        packet.push(request);
+    }
+}
+
+#[test]
+fn test_build_batch() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let payloads = vec![hex::decode("deadbeef").unwrap(); 100];
+    let packet = build(&payloads, &mut random);
+
+    assert!(packet.len() >= payloads.len());
+    assert!(packet.requests().iter().any(|request| request.method() == "eth_sendRawTransaction"));
+}
@@ -1,132 +1,46 @@
-use crate::{builder::Builder, cache::BuilderCache, logger::Logger};
+use crate::{
+    Runner,
+    builder::Builder,
+    signing,
+    transaction::{Transaction, TransactionRunner},
+};
 use alloy::{
-    consensus::{TxEip4844, TxEip4844WithSidecar, transaction::RlpEcdsaEncodableTx},
-    primitives::{Address, TxHash},
-    providers::{Provider, ProviderBuilder},
+    consensus::{TxEip4844, TxEip4844Variant, TxEip4844WithSidecar},
+    primitives::Address,
     rpc::types::TransactionRequest,
-    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
-    transports::http::reqwest::Url,
+    signers::k256::ecdsa::SigningKey,
 };
-use common::types::Backend;
-use mutator::Mutator;
-use rand::{SeedableRng, random_bool, rngs::StdRng};
+use rand::rngs::StdRng;
 
-pub struct BlobTransactionRunner {
-    pub sk: SigningKey,
-    pub seed: u64,
-    pub provider: Backend,
-    pub current_tx: Vec<u8>,
-    pub mutator: Mutator,
-    pub crash_counter: u64,
-    pub running: bool,
-    pub logger: Logger,
-    pub cache: BuilderCache,
-    pub sender: Address,
-}
+/// Blob is the transaction of the blob runner, an EIP-4844 transaction with
+/// blobs and KZG commitments, encoded in the envelope form of the config
+pub struct Blob;
 
-impl Builder for BlobTransactionRunner {
-    fn provider(&self) -> &Backend {
-        &self.provider
-    }
+pub type BlobTransactionRunner = TransactionRunner<Blob>;
 
-    fn cache(&self) -> &BuilderCache {
-        &self.cache
+impl Transaction for Blob {
+    const RUNNER: Runner = Runner::Blob;
+
+    async fn create(
+        runner: &BlobTransactionRunner,
+        random: &mut StdRng,
+    ) -> (TransactionRequest, Vec<u8>) {
+        let (request, tx) = runner.create_blob_transaction(random).await;
+        let form = runner.fork(random).blob_form(runner.config.blob_form);
+        (request, signing::blob_envelope(tx, form, &runner.sk, random))
     }
 
-    fn cache_mut(&mut self) -> &mut BuilderCache {
-        &mut self.cache
+    fn sign_mutated(
+        signing: signing::Signing,
+        bytes: &[u8],
+        sk: &SigningKey,
+        random: &mut StdRng,
+    ) -> Option<Vec<u8>> {
+        signing::sign_mutated::<TxEip4844Variant>(signing, bytes, sk, random)
     }
 }
 
 impl BlobTransactionRunner {
-    pub async fn new(
-        rpc_url: Url,
-        sk: SigningKey,
-        seed: u64,
-        max_operations_per_mutation: u64,
-    ) -> Self {
-        let provider = ProviderBuilder::new()
-            .wallet::<PrivateKeySigner>(sk.clone().into())
-            .connect_http(rpc_url);
-
-        let sender = Address::from_private_key(&sk);
-        let account = provider.get_account(sender).await.unwrap_or_default();
-        let cache = BuilderCache {
-            gas_price: provider.get_gas_price().await.unwrap_or_default(),
-            max_priority_fee: provider.get_max_priority_fee_per_gas().await.unwrap_or_default(),
-            max_fee_per_blob_gas: provider.get_blob_base_fee().await.unwrap_or_default(),
-            balance: account.balance,
-            nonce: account.nonce,
-            chain_id: provider.get_chain_id().await.unwrap_or_default(),
-        };
-
-        let mutator = Mutator::new(max_operations_per_mutation, seed);
-        let logger = Logger::new("blob").unwrap();
-
-        Self {
-            sk,
-            seed,
-            current_tx: vec![],
-            provider,
-            mutator,
-            crash_counter: 0,
-            running: false,
-            logger,
-            cache,
-            sender,
-        }
-    }
-
-    pub async fn run(&mut self) {
-        let mut random = StdRng::seed_from_u64(self.seed);
-        self.running = true;
-
-        loop {
-            // 10% chance to re-generate the transaction
-            if random_bool(0.1) || self.current_tx.is_empty() {
-                // 50% chance to update the cache
-                // This is to try to get further in the execution by bypassing
-                // common checks like gas > expected and so on
-                if random_bool(0.5) {
-                    self.cache.update(&self.provider, self.sender).await;
-                }
-
-                let (request, tx) = self.create_blob_transaction(&mut random).await;
-                tx.rlp_encode(&mut self.current_tx);
-
-                if let Err(err) = self.provider.send_transaction_unsafe(request).await {
-                    if self.logger.is_connection_refused_error(&err) {
-                        let current_tx = self.current_tx.clone();
-                        let _ = self.logger.generate_crash_report(&current_tx);
-
-                        self.crash_counter += 1;
-                        self.running = false;
-
-                        break;
-                    }
-                }
-            } else {
-                self.mutator.mutate(&mut self.current_tx);
-                if let Err(err) = self
-                    .provider
-                    .client()
-                    .request::<_, TxHash>("eth_sendRawTransaction", &self.current_tx)
-                    .await
-                {
-                    if self.logger.is_connection_refused_error(&err) {
-                        let current_tx = self.current_tx.clone();
-                        let _ = self.logger.generate_crash_report(&current_tx);
-
-                        self.crash_counter += 1;
-                        self.running = false;
-
-                        break;
-                    }
-                }
-            }
-        }
-    }
-
     pub async fn create_blob_transaction(
         &self,
         random: &mut StdRng,
@@ -136,25 +50,23 @@ impl BlobTransactionRunner {
 
         let to = self.to(random);
         let max_fee_per_gas = self.max_fee_per_gas(random);
-        let max_priority_fee_per_gas = self.max_priority_fee_per_gas(random).await;
+        let max_priority_fee_per_gas = self.max_priority_fee_per_gas(max_fee_per_gas, random).await;
         let max_fee_per_blob_gas = self.max_fee_per_blob_gas(random).await;
-        let gas_limit = self.gas(random);
         let value = self.value(random).await;
-        let input = self.input(random);
+        let input = self.input(&to, random);
         let nonce = self.nonce(random).await;
         let chain_id = self.chain_id(random).await;
         let access_list = self.access_list(random);
-        let blob_versioned_hashes = self.blob_versioned_hashes(random);
-        let sidecar = self.sidecar(random);
+        let (blob_versioned_hashes, sidecar) = self.blobs(random);
 
-        let request = TransactionRequest {
+        let mut request = TransactionRequest {
             from: Some(self.sender),
             to: Some(to),
             gas_price: None,
             max_fee_per_gas: Some(max_fee_per_gas),
             max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
             max_fee_per_blob_gas: Some(max_fee_per_blob_gas),
-            gas: Some(gas_limit),
+            gas: None,
             value: Some(value),
             input: input.clone(),
             nonce: Some(nonce),
@@ -166,6 +78,9 @@ impl BlobTransactionRunner {
             authorization_list: None,
         };
 
+        let gas_limit = self.gas(&request, random).await;
+        request.gas = Some(gas_limit);
+
         let tx = TxEip4844 {
             to: to.into_to().unwrap_or_else(|| Address::ZERO),
             chain_id,
@@ -188,8 +103,13 @@ impl BlobTransactionRunner {
 
 #[tokio::test]
 async fn test_blob_transaction_runner() {
+    use crate::config::RunnerConfig;
+    use alloy::transports::http::reqwest::Url;
+    use rand::SeedableRng;
+
     let mut rng = StdRng::seed_from_u64(1);
     let runner = BlobTransactionRunner::new(
+        "blob",
         "http://localhost:8545".parse::<Url>().unwrap(),
         SigningKey::from_slice(
             &alloy::hex::decode(
@@ -200,6 +120,7 @@ async fn test_blob_transaction_runner() {
         .unwrap(),
         1,
         1000,
+        RunnerConfig::default(),
     )
     .await;
     let tx = runner.create_blob_transaction(&mut rng).await;
@@ -1,30 +1,70 @@
 use alloy::{
     consensus::BlobTransactionSidecar,
-    eips::{eip4844::BYTES_PER_BLOB, eip7702::SignedAuthorization},
+    eips::{
+        eip4844::{
+            BLS_MODULUS_BYTES, BYTES_PER_BLOB, Blob, FIELD_ELEMENTS_PER_BLOB,
+            env_settings::EnvKzgSettings,
+        },
+        eip7702::SignedAuthorization,
+    },
     primitives::{Address, Bytes, FixedBytes, TxKind, U256},
-    rpc::types::{AccessList, AccessListItem, Authorization, TransactionInput},
+    providers::Provider,
+    rpc::types::{AccessList, AccessListItem, Authorization, TransactionInput, TransactionRequest},
+    signers::{SignerSync, local::PrivateKeySigner},
 };
 use common::{
     constants::{
         MAX_ACCESS_LIST_LENGTH, MAX_ACCESSED_KEYS_LENGTH, MAX_AUTHORIZATION_LIST_LENGTH,
         MAX_BLOB_SIDECAR_LENGTH, MAX_BLOB_VERSIONED_HASHES_LENGTH, MAX_GAS_LIMIT, MAX_INPUT_LENGTH,
-        MAX_TRANSACTION_TYPE,
+        MAX_SIGNED_AUTHORIZATIONS, STATIC_KEYS,
     },
+    parse_sk,
     types::Backend,
 };
-use rand::{Rng, RngCore, random_bool, rngs::StdRng};
+use rand::{Rng, RngCore, rngs::StdRng, seq::IndexedRandom};
 
-use crate::cache::BuilderCache;
+use crate::{
+    bytecode, cache::BuilderCache, config::RunnerConfig, fork::Fork, precompiles,
+    signing::SECP256K1N,
+};
 
 pub trait Builder {
     fn provider(&self) -> &Backend;
     fn cache(&self) -> &BuilderCache;
     fn cache_mut(&mut self) -> &mut BuilderCache;
+    fn sender(&self) -> Address;
+    fn config(&self) -> &RunnerConfig;
+
+    // ------------------------------------------------------------
+
+    /// Returns the fork the next part of a transaction is generated for,
+    /// which is the one before or after the fork of the node with the chance
+    /// set in the config
+    fn fork(&self, random: &mut StdRng) -> Fork {
+        let fork = self.cache().fork;
+        if !random.random_ratio(self.config().boundary, 100) {
+            return fork;
+        }
+
+        if random.random_bool(0.5) { fork.previous() } else { fork.next() }
+    }
 
     // ------------------------------------------------------------
 
     fn to(&self, random: &mut StdRng) -> TxKind {
-        if random_bool(0.5) {
+        // The fixture contracts are picked with the chance set in the config
+        let contracts = &self.cache().contracts;
+        if !contracts.is_empty() && random.random_ratio(self.config().fixtures, 100) {
+            return TxKind::Call(contracts[random.random_range(0..contracts.len())]);
+        }
+
+        // 10% chance to call a precompile, with an input built for it
+        if random.random_bool(0.1) {
+            let precompile = precompiles::PRECOMPILES.choose(random).unwrap();
+            return TxKind::Call(precompiles::address(*precompile));
+        }
+
+        if random.random_bool(0.5) {
             TxKind::Create
         } else {
             TxKind::Call({
@@ -39,42 +79,160 @@ pub trait Builder {
 
     #[allow(async_fn_in_trait)]
     async fn gas_price(&self, random: &mut StdRng) -> u128 {
-        if random_bool(0.85) { self.cache().gas_price } else { random.random::<u128>() }
+        if let Some(fees) = self.cache().pinned_fees {
+            return fees.gas_price;
+        }
+
+        let base_fee = self.cache().base_fee;
+        match random.random_range(0..20) {
+            0 => base_fee,
+            1 => base_fee.saturating_sub(1),
+            2 => self.overflowing_fee(random),
+            3 => random.random::<u128>(),
+            _ => self.cache().gas_price,
+        }
     }
 
     // ------------------------------------------------------------
 
-    // [nethoxa] this should be better implemented
     fn max_fee_per_gas(&self, random: &mut StdRng) -> u128 {
-        random.random::<u128>()
+        // Twice the base fee plus the tip, as wallets do, most of the time,
+        // and the edges of the EIP-1559 checks otherwise
+        if let Some(fees) = self.cache().pinned_fees {
+            return fees.max_fee_per_gas;
+        }
+
+        let base_fee = self.cache().base_fee;
+        match random.random_range(0..20) {
+            0 | 1 => base_fee,
+            2 | 3 => base_fee.saturating_sub(1),
+            4 => self.overflowing_fee(random),
+            5 => random.random::<u128>(),
+            _ => base_fee.saturating_mul(2).saturating_add(self.cache().max_priority_fee),
+        }
+    }
+
+    /// Returns a fee whose product with any gas limit overflows 128 bits, or
+    /// 256 bits once added the value
+    fn overflowing_fee(&self, random: &mut StdRng) -> u128 {
+        match random.random_range(0..3) {
+            0 => u128::MAX,
+            1 => u128::MAX / random.random_range(21_000..=MAX_GAS_LIMIT as u128) + 1,
+            _ => random.random_range(u64::MAX as u128..=u128::MAX),
+        }
     }
 
     // ------------------------------------------------------------
 
     #[allow(async_fn_in_trait)]
-    async fn max_priority_fee_per_gas(&self, random: &mut StdRng) -> u128 {
-        if random_bool(0.85) { self.cache().max_priority_fee } else { random.random::<u128>() }
+    async fn max_priority_fee_per_gas(&self, max_fee_per_gas: u128, random: &mut StdRng) -> u128 {
+        if let Some(fees) = self.cache().pinned_fees {
+            return fees.max_priority_fee_per_gas;
+        }
+
+        match random.random_range(0..20) {
+            // A tip above the maximum fee, which nodes reject
+            0 => max_fee_per_gas.saturating_add(1),
+            // All the maximum fee over the base fee
+            1 => max_fee_per_gas,
+            2 => max_fee_per_gas.saturating_sub(self.cache().base_fee),
+            3 => 0,
+            4 => random.random::<u128>(),
+            _ => self.cache().max_priority_fee,
+        }
     }
 
     // ------------------------------------------------------------
 
     #[allow(async_fn_in_trait)]
     async fn max_fee_per_blob_gas(&self, random: &mut StdRng) -> u128 {
-        if random_bool(0.85) { self.cache().max_fee_per_blob_gas } else { random.random::<u128>() }
+        if let Some(fees) = self.cache().pinned_fees {
+            return fees.max_fee_per_blob_gas;
+        }
+
+        let blob_base_fee = self.cache().blob_base_fee;
+        match random.random_range(0..20) {
+            0 | 1 => blob_base_fee,
+            // Below the blob base fee, so the transaction cannot be included
+            2 | 3 => blob_base_fee.saturating_sub(1),
+            4 => self.overflowing_fee(random),
+            5 => random.random::<u128>(),
+            _ => self.cache().max_fee_per_blob_gas,
+        }
     }
 
     // ------------------------------------------------------------
 
-    // [nethoxa] should implement a call to gas estimation
-    fn gas(&self, random: &mut StdRng) -> u64 {
-        random.random_range(0..=MAX_GAS_LIMIT * 2)
+    #[allow(async_fn_in_trait)]
+    async fn gas(&self, request: &TransactionRequest, random: &mut StdRng) -> u64 {
+        // Limits are picked around the boundaries where the node rejects the
+        // transaction or runs out of gas with the chance set in the config
+        if !random.random_ratio(self.config().estimate, 100) {
+            // Up to twice the cap of the fork, or of the block before Osaka
+            let cap = match self.fork(random).max_transaction_gas() {
+                Some(cap) => cap,
+                None if self.cache().block_gas_limit > 0 => self.cache().block_gas_limit,
+                None => MAX_GAS_LIMIT,
+            };
+            return random.random_range(0..=cap * 2);
+        }
+
+        // The cap of EIP-7825 only applies since Osaka, before which the
+        // block gas limit is the only one
+        let fork = self.cache().fork;
+        let cap = fork.max_transaction_gas().unwrap_or(self.cache().block_gas_limit);
+        let intrinsic = self.intrinsic_gas(request);
+        match random.random_range(0..8) {
+            0 | 1 => {
+                // The request is estimated without its fees and nonce, which
+                // are random most of the time and would make it fail
+                let estimation = TransactionRequest {
+                    from: request.from,
+                    to: request.to,
+                    value: request.value,
+                    input: request.input.clone(),
+                    access_list: request.access_list.clone(),
+                    authorization_list: request.authorization_list.clone(),
+                    ..Default::default()
+                };
+                let estimate = self.provider().estimate_gas(estimation).await.unwrap_or(intrinsic);
+                if random.random_bool(0.5) { estimate } else { estimate.saturating_sub(1) }
+            }
+            2 => intrinsic,
+            3 => intrinsic.saturating_sub(1),
+            4 => self.cache().block_gas_limit,
+            5 => self.cache().block_gas_limit.saturating_add(1),
+            6 => cap.saturating_sub(1),
+            _ => cap.saturating_add(random.random_range(0..=1)),
+        }
+    }
+
+    /// Returns the intrinsic gas of a request, that is, the gas charged
+    /// before execution, including the calldata floor of EIP-7623 on the
+    /// forks that have it
+    fn intrinsic_gas(&self, request: &TransactionRequest) -> u64 {
+        let input = request.input.input().map(|input| input.as_ref()).unwrap_or_default();
+        let zeros = input.iter().filter(|byte| **byte == 0).count() as u64;
+        let tokens = zeros + (input.len() as u64 - zeros) * 4;
+
+        let mut gas = 21_000 + tokens * 4;
+        if request.to.is_none_or(|to| to.is_create()) {
+            // EIP-3860 charges 2 gas per word of initcode
+            gas += 32_000 + (input.len() as u64).div_ceil(32) * 2;
+        }
+        for item in request.access_list.iter().flat_map(|list| list.0.iter()) {
+            gas += 2_400 + item.storage_keys.len() as u64 * 1_900;
+        }
+        gas += request.authorization_list.as_ref().map_or(0, |list| list.len() as u64) * 25_000;
+
+        if self.cache().fork.calldata_floor() { gas.max(21_000 + tokens * 10) } else { gas }
     }
 
     // ------------------------------------------------------------
 
     #[allow(async_fn_in_trait)]
     async fn value(&self, random: &mut StdRng) -> U256 {
-        if random_bool(0.85) {
+        if random.random_bool(0.85) {
             self.cache().balance / U256::from(100_000_000)
         } else {
             self.random_u256(random)
@@ -89,8 +247,27 @@ pub trait Builder {
 
     // ------------------------------------------------------------
 
-    fn input(&self, random: &mut StdRng) -> TransactionInput {
-        if random_bool(0.2) {
+    fn input(&self, to: &TxKind, random: &mut StdRng) -> TransactionInput {
+        // 80% chance for calls to precompiles to carry an input in their format
+        if let Some(precompile) = to.to().and_then(precompiles::precompile) {
+            if random.random_bool(0.8) {
+                return TransactionInput::new(precompiles::input(precompile, random).into());
+            }
+        }
+
+        // 50% chance for create transactions to deploy a generated program,
+        // and 20% for calls to carry one, which proxies and other deployed
+        // contracts execute
+        let chance = if to.is_create() { 0.5 } else { 0.2 };
+        if random.random_bool(chance) {
+            let contracts = &self.cache().contracts;
+            let code = if to.is_create() {
+                bytecode::initcode(contracts, random)
+            } else {
+                bytecode::program(contracts, random)
+            };
+            TransactionInput::new(code.into())
+        } else if random.random_bool(0.2) {
             let length = random.random_range(0..=MAX_INPUT_LENGTH);
             TransactionInput::new(self.random_bytes(length, random))
         } else {
@@ -102,20 +279,29 @@ pub trait Builder {
 
     #[allow(async_fn_in_trait)]
     async fn nonce(&self, random: &mut StdRng) -> u64 {
-        if random_bool(0.90) { self.cache().nonce } else { random.next_u64() }
+        // The nonce manager already picks nonces off the pending one
+        if self.cache().pinned_nonce || random.random_bool(0.90) {
+            self.cache().nonce
+        } else {
+            random.next_u64()
+        }
     }
 
     // ------------------------------------------------------------
 
     #[allow(async_fn_in_trait)]
     async fn chain_id(&self, random: &mut StdRng) -> u64 {
-        if random_bool(0.95) { self.cache().chain_id } else { random.next_u64() }
+        if random.random_bool(0.95) { self.cache().chain_id } else { random.next_u64() }
     }
 
     // ------------------------------------------------------------
 
     fn access_list(&self, random: &mut StdRng) -> AccessList {
-        if random_bool(0.2) { self.random_access_list(random) } else { AccessList::from(vec![]) }
+        if random.random_bool(0.2) {
+            self.random_access_list(random)
+        } else {
+            AccessList::from(vec![])
+        }
     }
 
     fn random_access_list(&self, random: &mut StdRng) -> AccessList {
@@ -151,13 +337,14 @@ pub trait Builder {
 
     fn transaction_type(&self, random: &mut StdRng) -> u8 {
         // [nethoxa] should we send tx with wrong transaction type?
-        random.random_range(0..MAX_TRANSACTION_TYPE)
+        let types = self.fork(random).transaction_types();
+        random.random_range(0..types)
     }
 
     // ------------------------------------------------------------
 
     fn blob_versioned_hashes(&self, random: &mut StdRng) -> Vec<FixedBytes<32>> {
-        if random_bool(0.2) { self.random_blob_versioned_hashes(random) } else { vec![] }
+        if random.random_bool(0.2) { self.random_blob_versioned_hashes(random) } else { vec![] }
     }
 
     fn random_blob_versioned_hashes(&self, random: &mut StdRng) -> Vec<FixedBytes<32>> {
@@ -182,7 +369,7 @@ pub trait Builder {
     // ------------------------------------------------------------
 
     fn sidecar(&self, random: &mut StdRng) -> BlobTransactionSidecar {
-        if random_bool(0.2) {
+        if random.random_bool(0.2) {
             self.random_sidecar(random)
         } else {
             BlobTransactionSidecar::new(vec![], vec![], vec![])
@@ -190,7 +377,7 @@ pub trait Builder {
     }
 
     fn random_sidecar(&self, random: &mut StdRng) -> BlobTransactionSidecar {
-        let same_length = random_bool(0.75);
+        let same_length = random.random_bool(0.75);
         if same_length {
             let length = random.random_range(0..MAX_BLOB_SIDECAR_LENGTH);
             let mut blobs = vec![];
@@ -280,8 +467,214 @@ pub trait Builder {
 
     // ------------------------------------------------------------
 
-    fn authorization_list(&self, random: &mut StdRng) -> Vec<SignedAuthorization> {
-        if random_bool(0.2) { self.random_authorization_list(random) } else { vec![] }
+    fn blobs(&self, random: &mut StdRng) -> (Vec<FixedBytes<32>>, BlobTransactionSidecar) {
+        // 50% chance to build blobs that pass KZG verification, so that the
+        // checks after it in the blob pool are reached
+        if random.random_bool(0.5) {
+            let (mut hashes, mut sidecar) = self.kzg_blobs(random);
+
+            // 30% chance to break exactly one thing in them
+            if random.random_bool(0.3) && !sidecar.blobs.is_empty() {
+                self.corrupt_blobs(&mut hashes, &mut sidecar, random);
+            }
+
+            (hashes, sidecar)
+        } else {
+            (self.blob_versioned_hashes(random), self.sidecar(random))
+        }
+    }
+
+    fn kzg_blobs(&self, random: &mut StdRng) -> (Vec<FixedBytes<32>>, BlobTransactionSidecar) {
+        // 10% chance for one blob more than the fork allows per transaction
+        let max = self.fork(random).max_blobs_per_tx();
+        let length = if random.random_bool(0.1) { max + 1 } else { random.random_range(1..=max) };
+        let mut blobs = vec![];
+
+        for _ in 0..length {
+            let mut blob = vec![0u8; BYTES_PER_BLOB];
+            random.fill_bytes(&mut blob);
+
+            // Field elements are big endian, so a zero first byte keeps each
+            // of them below the BLS modulus
+            for element in blob.chunks_mut(32) {
+                element[0] = 0;
+            }
+
+            blobs.push(Blob::from_slice(&blob));
+        }
+
+        // Commitments and proofs are computed with the mainnet trusted setup
+        // bundled with alloy
+        match BlobTransactionSidecar::try_from_blobs_with_settings(
+            blobs,
+            EnvKzgSettings::Default.get(),
+        ) {
+            Ok(sidecar) => (sidecar.versioned_hashes().collect(), sidecar),
+            Err(_) => (self.blob_versioned_hashes(random), self.sidecar(random)),
+        }
+    }
+
+    fn corrupt_blobs(
+        &self,
+        hashes: &mut [FixedBytes<32>],
+        sidecar: &mut BlobTransactionSidecar,
+        random: &mut StdRng,
+    ) {
+        let index = random.random_range(0..sidecar.blobs.len());
+        let other = random.random_range(0..sidecar.blobs.len());
+
+        match random.random_range(0..4) {
+            // One bad proof, either the proof of another blob or a flipped bit
+            0 => {
+                if index != other {
+                    sidecar.proofs[index] = sidecar.proofs[other];
+                } else {
+                    sidecar.proofs[index][random.random_range(0..48)] ^=
+                        1 << random.random_range(0..8);
+                }
+            }
+            // Two commitments swapped, or a proof used as a commitment
+            1 => {
+                if index != other {
+                    sidecar.commitments.swap(index, other);
+                } else {
+                    sidecar.commitments[index] = sidecar.proofs[index];
+                }
+            }
+            // A versioned hash with a version byte other than the KZG one
+            2 => {
+                hashes[index][0] = random.random_range(2..=u8::MAX);
+            }
+            // A field element equal to or above the BLS modulus
+            _ => {
+                let offset = random.random_range(0..FIELD_ELEMENTS_PER_BLOB as usize) * 32;
+                let element = if random.random_bool(0.5) {
+                    BLS_MODULUS_BYTES
+                } else {
+                    FixedBytes::new([0xff; 32])
+                };
+
+                sidecar.blobs[index][offset..offset + 32].copy_from_slice(element.as_slice());
+            }
+        }
+    }
+
+    // ------------------------------------------------------------
+
+    #[allow(async_fn_in_trait)]
+    async fn authorization_list(&self, random: &mut StdRng) -> Vec<SignedAuthorization> {
+        match random.random_range(0..10) {
+            0..5 => self.signed_authorization_list(random).await,
+            5..7 => self.random_authorization_list(random),
+            _ => vec![],
+        }
+    }
+
+    // Authorizations actually signed by known keys, so that the authorities
+    // are recoverable and the delegations get executed
+    #[allow(async_fn_in_trait)]
+    async fn signed_authorization_list(&self, random: &mut StdRng) -> Vec<SignedAuthorization> {
+        let length = random.random_range(1..=MAX_SIGNED_AUTHORIZATIONS);
+        let mut authorizations = vec![];
+
+        for _ in 0..length {
+            let sk = parse_sk(STATIC_KEYS[random.random_range(0..STATIC_KEYS.len())]).unwrap();
+            let signer = PrivateKeySigner::from_signing_key(sk);
+            let authority = signer.address();
+
+            let chain_id = if random.random_bool(0.5) {
+                U256::ZERO
+            } else {
+                U256::from(self.cache().chain_id)
+            };
+            let nonce = self.authority_nonce(authority).await;
+            let nonce = match random.random_range(0..10) {
+                0 => nonce.wrapping_sub(1),
+                1 => nonce.wrapping_add(1),
+                _ => nonce,
+            };
+            let address = self.delegation_target(random);
+
+            let auth = Authorization { chain_id, address, nonce };
+            let Ok(signature) = signer.sign_hash_sync(&auth.signature_hash()) else {
+                continue;
+            };
+            let signed = auth.into_signed(signature);
+
+            // 20% chance to break exactly one thing in it
+            if random.random_bool(0.2) {
+                authorizations.push(self.corrupt_authorization(signed, random));
+            } else {
+                authorizations.push(signed);
+            }
+        }
+
+        authorizations
+    }
+
+    #[allow(async_fn_in_trait)]
+    async fn authority_nonce(&self, authority: Address) -> u64 {
+        // The nonce of the sender is increased before the authorizations of
+        // its own transaction are processed
+        if authority == self.sender() {
+            self.cache().nonce + 1
+        } else {
+            self.provider().get_transaction_count(authority).await.unwrap_or_default()
+        }
+    }
+
+    fn delegation_target(&self, random: &mut StdRng) -> Address {
+        let contracts = &self.cache().contracts;
+        match random.random_range(0..10) {
+            // Delegating to the zero address revokes the delegation
+            0 => Address::ZERO,
+            1 => self.random_address(random),
+            2 | 3 => Address::with_last_byte(random.random_range(1..=0x11)),
+            _ if !contracts.is_empty() => contracts[random.random_range(0..contracts.len())],
+            _ => Address::with_last_byte(random.random_range(1..=0x11)),
+        }
+    }
+
+    fn corrupt_authorization(
+        &self,
+        signed: SignedAuthorization,
+        random: &mut StdRng,
+    ) -> SignedAuthorization {
+        let y_parity = signed.y_parity();
+        let r = signed.r();
+        let s = signed.s();
+        let mut auth = signed.strip_signature();
+
+        match random.random_range(0..7) {
+            // Wrong parity, which recovers another authority
+            0 => return SignedAuthorization::new_unchecked(auth, y_parity ^ 1, r, s),
+            1 => {
+                return SignedAuthorization::new_unchecked(
+                    auth,
+                    random.random_range(2..=u8::MAX),
+                    r,
+                    s,
+                );
+            }
+            // The malleable twin of the signature, with high `s`
+            2 => return SignedAuthorization::new_unchecked(auth, y_parity ^ 1, r, SECP256K1N - s),
+            3 => return SignedAuthorization::new_unchecked(auth, y_parity, U256::ZERO, s),
+            // A chain id other than zero and the one of the node
+            4 => auth.chain_id = U256::from(self.cache().chain_id.wrapping_add(1)),
+            // The highest nonce, which EIP-7702 forbids
+            5 => auth.nonce = u64::MAX,
+            // A random signature over the same authorization
+            _ => {
+                return SignedAuthorization::new_unchecked(
+                    auth,
+                    y_parity,
+                    self.random_u256(random),
+                    self.random_u256(random),
+                );
+            }
+        }
+
+        SignedAuthorization::new_unchecked(auth, y_parity, r, s)
     }
 
     fn random_authorization_list(&self, random: &mut StdRng) -> Vec<SignedAuthorization> {
@@ -0,0 +1,454 @@
+use crate::precompiles::PRECOMPILES;
+use alloy::primitives::{Address, U256};
+use common::constants::{MAX_PROGRAM_DEPTH, MAX_PROGRAM_SNIPPETS};
+use rand::{Rng, RngCore, rngs::StdRng, seq::IndexedRandom};
+
+const STOP: u8 = 0x00;
+const SUB: u8 = 0x03;
+const ADDMOD: u8 = 0x08;
+const MULMOD: u8 = 0x09;
+const ISZERO: u8 = 0x15;
+const NOT: u8 = 0x19;
+const KECCAK256: u8 = 0x20;
+const ADDRESS: u8 = 0x30;
+const CALLDATALOAD: u8 = 0x35;
+const CODECOPY: u8 = 0x39;
+const BLOBHASH: u8 = 0x49;
+const POP: u8 = 0x50;
+const MLOAD: u8 = 0x51;
+const MSTORE: u8 = 0x52;
+const MSTORE8: u8 = 0x53;
+const SLOAD: u8 = 0x54;
+const SSTORE: u8 = 0x55;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const GAS: u8 = 0x5a;
+const JUMPDEST: u8 = 0x5b;
+const TLOAD: u8 = 0x5c;
+const TSTORE: u8 = 0x5d;
+const MCOPY: u8 = 0x5e;
+const PUSH0: u8 = 0x5f;
+const PUSH1: u8 = 0x60;
+const PUSH2: u8 = 0x61;
+const DUP1: u8 = 0x80;
+const SWAP1: u8 = 0x90;
+const LOG0: u8 = 0xa0;
+const CALL: u8 = 0xf1;
+const RETURN: u8 = 0xf3;
+const DELEGATECALL: u8 = 0xf4;
+const CREATE2: u8 = 0xf5;
+const STATICCALL: u8 = 0xfa;
+const REVERT: u8 = 0xfd;
+const INVALID: u8 = 0xfe;
+const SELFDESTRUCT: u8 = 0xff;
+
+/// Opcodes taking two words and pushing one
+const BINARY: [u8; 21] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x0a, 0x0b, 0x10, 0x11, 0x12, 0x13, 0x14, 0x16, 0x17,
+    0x18, 0x1a, 0x1b, 0x1c, 0x1d,
+];
+
+/// Opcodes taking nothing and pushing one word
+const NULLARY: [u8; 20] = [
+    0x30, 0x32, 0x33, 0x34, 0x36, 0x38, 0x3a, 0x3d, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x4a, 0x58, 0x59, 0x5a,
+];
+
+/// Opcodes taking an address and pushing one word
+const ACCOUNT: [u8; 3] = [
+    0x31, 0x3b, 0x3f,
+];
+
+/// Memory is kept within the first kilobyte, so that expansion does not eat
+/// all the gas of the transaction
+const MEMORY_RANGE: u64 = 1024;
+
+/// Generates a runtime program, that is, a stack-balanced sequence of
+/// snippets ending with a terminating opcode. Every snippet leaves the stack
+/// as it found it, and every jump lands on a `JUMPDEST`, so execution only
+/// stops where the program says so or when it runs out of gas.
+///
+/// # Arguments
+///
+/// * `contracts` - Deployed contracts the program can call into.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The bytecode of the program.
+pub fn program(contracts: &[Address], random: &mut StdRng) -> Vec<u8> {
+    let mut code = vec![];
+    body(&mut code, contracts, 0, random);
+    terminate(&mut code, random);
+    code
+}
+
+/// Generates the initcode of a contract: a constructor that runs a program
+/// of its own and then returns a generated runtime program
+///
+/// # Arguments
+///
+/// * `contracts` - Deployed contracts the programs can call into.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The initcode, to be used as the input of a create transaction.
+pub fn initcode(contracts: &[Address], random: &mut StdRng) -> Vec<u8> {
+    let mut code = vec![];
+    // 50% chance to do something in the constructor too
+    if random.random_bool(0.5) {
+        body(&mut code, contracts, 0, random);
+    }
+
+    let runtime = program(contracts, random);
+    deploy(&mut code, &runtime);
+    code
+}
+
+/// Returns the initcode that deploys the given runtime code as is
+pub fn deployment(runtime: &[u8]) -> Vec<u8> {
+    let mut code = vec![];
+    deploy(&mut code, runtime);
+    code
+}
+
+// Helper function to append the code that returns `runtime`, which is placed
+// right after it, as the code of the contract
+fn deploy(code: &mut Vec<u8>, runtime: &[u8]) {
+    // PUSH2 len, DUP1, PUSH2 offset, PUSH0, CODECOPY, PUSH0, RETURN
+    let offset = code.len() + 11;
+    code.push(PUSH2);
+    code.extend_from_slice(&(runtime.len() as u16).to_be_bytes());
+    code.push(DUP1);
+    code.push(PUSH2);
+    code.extend_from_slice(&(offset as u16).to_be_bytes());
+    code.extend_from_slice(&[
+        PUSH0, CODECOPY, PUSH0, RETURN,
+    ]);
+    code.extend_from_slice(runtime);
+}
+
+// Helper function to append a sequence of snippets
+fn body(code: &mut Vec<u8>, contracts: &[Address], depth: usize, random: &mut StdRng) {
+    for _ in 0..random.random_range(1..=MAX_PROGRAM_SNIPPETS) {
+        snippet(code, contracts, depth, random);
+    }
+}
+
+// Helper function to append a single stack-neutral snippet
+fn snippet(code: &mut Vec<u8>, contracts: &[Address], depth: usize, random: &mut StdRng) {
+    // Jumps, loops and contract creations nest other snippets
+    let kinds = if depth < MAX_PROGRAM_DEPTH { 14 } else { 11 };
+
+    match random.random_range(0..kinds) {
+        // Arithmetic and bitwise operations
+        0 => {
+            match random.random_range(0..3) {
+                0 => {
+                    push_word(code, random);
+                    code.push(
+                        *[
+                            ISZERO, NOT,
+                        ]
+                        .choose(random)
+                        .unwrap(),
+                    );
+                }
+                1 => {
+                    push_word(code, random);
+                    push_word(code, random);
+                    code.push(*BINARY.choose(random).unwrap());
+                }
+                _ => {
+                    push_word(code, random);
+                    push_word(code, random);
+                    push_word(code, random);
+                    code.push(
+                        *[
+                            ADDMOD, MULMOD,
+                        ]
+                        .choose(random)
+                        .unwrap(),
+                    );
+                }
+            }
+            code.push(POP);
+        }
+        // Environment and block information
+        1 => {
+            if random.random_bool(0.7) {
+                code.push(*NULLARY.choose(random).unwrap());
+            } else {
+                push_address(code, contracts, random);
+                code.push(*ACCOUNT.choose(random).unwrap());
+            }
+            code.push(POP);
+        }
+        // Calldata, so that calls to the program take different paths
+        2 => {
+            push(code, U256::from(random.random_range(0..256u64)));
+            code.push(CALLDATALOAD);
+            code.push(POP);
+        }
+        // Memory
+        3 => match random.random_range(0..3) {
+            0 => {
+                push_word(code, random);
+                push_offset(code, random);
+                code.push(
+                    *[
+                        MSTORE, MSTORE8,
+                    ]
+                    .choose(random)
+                    .unwrap(),
+                );
+            }
+            1 => {
+                push_offset(code, random);
+                code.push(MLOAD);
+                code.push(POP);
+            }
+            _ => {
+                push_offset(code, random);
+                push_offset(code, random);
+                push_offset(code, random);
+                code.push(MCOPY);
+            }
+        },
+        // Storage and transient storage
+        4 => {
+            let key = U256::from(random.random_range(0..16u64));
+            if random.random_bool(0.5) {
+                push_word(code, random);
+                push(code, key);
+                code.push(
+                    *[
+                        SSTORE, TSTORE,
+                    ]
+                    .choose(random)
+                    .unwrap(),
+                );
+            } else {
+                push(code, key);
+                code.push(
+                    *[
+                        SLOAD, TLOAD,
+                    ]
+                    .choose(random)
+                    .unwrap(),
+                );
+                code.push(POP);
+            }
+        }
+        // Hashing
+        5 => {
+            push_offset(code, random);
+            push_offset(code, random);
+            code.push(KECCAK256);
+            code.push(POP);
+        }
+        // Versioned hashes of the blobs of the transaction, mostly in range
+        6 => {
+            push(code, U256::from(random.random_range(0..=9u64)));
+            code.push(BLOBHASH);
+            code.push(POP);
+        }
+        // Logs
+        7 => {
+            let topics = random.random_range(0..=4);
+            for _ in 0..topics {
+                push_word(code, random);
+            }
+            push_offset(code, random);
+            push_offset(code, random);
+            code.push(LOG0 + topics);
+        }
+        // Calls to precompiles, deployed contracts or the program itself
+        8..=10 => call(code, contracts, random),
+        // Forward jumps over a nested snippet
+        11 => {
+            let conditional = random.random_bool(0.5);
+            if conditional {
+                push(code, U256::from(random.random_range(0..2u64)));
+            }
+            let dest = jump_target(code);
+            code.push(if conditional { JUMPI } else { JUMP });
+
+            snippet(code, contracts, depth + 1, random);
+            patch(code, dest);
+            code.push(JUMPDEST);
+        }
+        // Bounded loops, with the counter on the stack
+        12 => {
+            push(code, U256::from(random.random_range(1..=8u64)));
+            let start = code.len();
+            code.push(JUMPDEST);
+
+            snippet(code, contracts, depth + 1, random);
+
+            // PUSH1 1, SWAP1, SUB, DUP1, PUSH2 start, JUMPI, POP
+            code.extend_from_slice(&[
+                PUSH1, 1, SWAP1, SUB, DUP1, PUSH2,
+            ]);
+            code.extend_from_slice(&(start as u16).to_be_bytes());
+            code.extend_from_slice(&[
+                JUMPI, POP,
+            ]);
+        }
+        // Contract creation with CREATE2, out of a program written to memory
+        _ => {
+            let mut child = vec![];
+            snippet(&mut child, contracts, depth + 1, random);
+            deploy(&mut child, &[STOP]);
+
+            let offset = random.random_range(0..MEMORY_RANGE) & !31;
+            for (i, chunk) in child.chunks(32).enumerate() {
+                let mut word = [0u8; 32];
+                word[..chunk.len()].copy_from_slice(chunk);
+                push(code, U256::from_be_bytes(word));
+                push(code, U256::from(offset + 32 * i as u64));
+                code.push(MSTORE);
+            }
+
+            push_word(code, random);
+            push(code, U256::from(child.len()));
+            push(code, U256::from(offset));
+            push(code, U256::ZERO);
+            code.push(CREATE2);
+            code.push(POP);
+        }
+    }
+}
+
+// Helper function to append a call, with its arguments and return data in
+// memory, and pop its success flag
+fn call(code: &mut Vec<u8>, contracts: &[Address], random: &mut StdRng) {
+    let opcode = *[
+        CALL,
+        STATICCALL,
+        DELEGATECALL,
+    ]
+    .choose(random)
+    .unwrap();
+
+    push_offset(code, random);
+    push_offset(code, random);
+    push_offset(code, random);
+    push_offset(code, random);
+    if opcode == CALL {
+        push(code, U256::from(random.random_range(0..2u64)));
+    }
+    push_address(code, contracts, random);
+    // All of the gas left
+    code.push(GAS);
+    code.push(opcode);
+    code.push(POP);
+}
+
+// Helper function to append the opcode the program ends with
+fn terminate(code: &mut Vec<u8>, random: &mut StdRng) {
+    match random.random_range(0..10) {
+        0..=3 => code.push(STOP),
+        4..=6 => {
+            push_offset(code, random);
+            push_offset(code, random);
+            code.push(RETURN);
+        }
+        7 => {
+            push_offset(code, random);
+            push_offset(code, random);
+            code.push(REVERT);
+        }
+        8 => {
+            push_address(code, &[], random);
+            code.push(SELFDESTRUCT);
+        }
+        _ => code.push(INVALID),
+    }
+}
+
+// Helper function to append a `PUSH2` whose destination is patched later on
+fn jump_target(code: &mut Vec<u8>) -> usize {
+    code.push(PUSH2);
+    code.extend_from_slice(&[
+        0, 0,
+    ]);
+    code.len() - 2
+}
+
+// Helper function to point the `PUSH2` at `position` to the end of the code
+fn patch(code: &mut [u8], position: usize) {
+    let dest = (code.len() as u16).to_be_bytes();
+    code[position..position + 2].copy_from_slice(&dest);
+}
+
+// Helper function to append the shortest `PUSH` of the given value
+fn push(code: &mut Vec<u8>, value: U256) {
+    let bytes = value.to_be_bytes::<32>();
+    let length = 32 - value.leading_zeros() / 8;
+    if length == 0 {
+        code.push(PUSH0);
+    } else {
+        code.push(PUSH1 + length as u8 - 1);
+        code.extend_from_slice(&bytes[32 - length..]);
+    }
+}
+
+// Helper function to push a word, mostly small or at the edges of the range
+fn push_word(code: &mut Vec<u8>, random: &mut StdRng) {
+    let word = match random.random_range(0..6) {
+        0 => U256::ZERO,
+        1 => U256::MAX,
+        2 => U256::from(1) << random.random_range(0..256),
+        3 => U256::from_be_bytes(random.random::<[u8; 32]>()),
+        _ => U256::from(random.random::<u8>()),
+    };
+    push(code, word);
+}
+
+// Helper function to push an offset or length within `MEMORY_RANGE`
+fn push_offset(code: &mut Vec<u8>, random: &mut StdRng) {
+    push(code, U256::from(random.random_range(0..MEMORY_RANGE)));
+}
+
+// Helper function to push the address of a precompile, a deployed contract,
+// the program itself or a random account
+fn push_address(code: &mut Vec<u8>, contracts: &[Address], random: &mut StdRng) {
+    match random.random_range(0..4) {
+        0 if !contracts.is_empty() => {
+            push(code, U256::from_be_slice(contracts.choose(random).unwrap().as_slice()))
+        }
+        1 => code.push(ADDRESS),
+        2 => {
+            let mut address = [0u8; 20];
+            random.fill_bytes(&mut address);
+            push(code, U256::from_be_slice(&address));
+        }
+        _ => push(code, U256::from(*PRECOMPILES.choose(random).unwrap())),
+    }
+}
+
+#[test]
+fn test_program_jumps() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    for _ in 0..64 {
+        let code = program(&[], &mut random);
+
+        // Every PUSH2 right before a JUMP or JUMPI points at a JUMPDEST
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = code[pc];
+            if opcode == PUSH2 && matches!(code.get(pc + 3), Some(&JUMP) | Some(&JUMPI)) {
+                let dest = u16::from_be_bytes([
+                    code[pc + 1],
+                    code[pc + 2],
+                ]) as usize;
+                assert_eq!(code[dest], JUMPDEST);
+            }
+            pc += if (PUSH1..=0x7f).contains(&opcode) { (opcode - PUSH1) as usize + 2 } else { 1 };
+        }
+    }
+}
@@ -1,9 +1,16 @@
+use crate::{fork::Fork, nonces::Fees};
 use alloy::{
+    eips::{
+        BlockNumberOrTag,
+        eip4844::{BLOB_TX_MIN_BLOB_GASPRICE, fake_exponential},
+    },
     primitives::{Address, U256},
     providers::Provider,
 };
-use common::types::Backend;
+use common::{constants::MAX_GAS_LIMIT, types::Backend};
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct BuilderCache {
     pub gas_price: u128,
     pub max_priority_fee: u128,
@@ -11,6 +18,30 @@ pub struct BuilderCache {
     pub balance: U256,
     pub nonce: u64,
     pub chain_id: u64,
+    // Gas limit, base fee, excess blob gas and blob base fee of the latest
+    // block, which gas limits and fees are picked around
+    #[serde(default)]
+    pub block_gas_limit: u64,
+    #[serde(default)]
+    pub base_fee: u128,
+    #[serde(default)]
+    pub excess_blob_gas: u64,
+    #[serde(default)]
+    pub blob_base_fee: u128,
+    // Fork the node is on, which decides what is valid to generate
+    #[serde(default)]
+    pub fork: Fork,
+    // Test contracts deployed by the runner, which transactions and
+    // authorizations can target
+    #[serde(default)]
+    pub contracts: Vec<Address>,
+    // Nonce and fees picked by the nonce manager for the transaction being
+    // built, which the builder does not randomize. They only last while the
+    // transaction is built, so they are not part of checkpoints
+    #[serde(skip)]
+    pub pinned_nonce: bool,
+    #[serde(skip)]
+    pub pinned_fees: Option<Fees>,
 }
 
 impl BuilderCache {
@@ -23,5 +54,27 @@ impl BuilderCache {
         self.balance = account.balance;
         self.nonce = account.nonce;
         self.chain_id = provider.get_chain_id().await.unwrap_or_default();
+        self.update_block(provider).await;
+    }
+
+    /// Updates the fields taken from the latest block. The blob base fee is
+    /// derived from the excess blob gas if the node does not return it.
+    pub async fn update_block(&mut self, provider: &Backend) {
+        let block = provider.get_block_by_number(BlockNumberOrTag::Latest).await.ok().flatten();
+        let header = block.map(|block| block.header);
+
+        self.block_gas_limit = header.as_ref().map_or(MAX_GAS_LIMIT, |header| header.gas_limit);
+        self.base_fee =
+            header.as_ref().and_then(|header| header.base_fee_per_gas).unwrap_or_default() as u128;
+        self.excess_blob_gas =
+            header.as_ref().and_then(|header| header.excess_blob_gas).unwrap_or_default();
+        self.blob_base_fee = match provider.get_blob_base_fee().await {
+            Ok(blob_base_fee) => blob_base_fee,
+            Err(_) => fake_exponential(
+                BLOB_TX_MIN_BLOB_GASPRICE,
+                self.excess_blob_gas as u128,
+                self.fork.blob_update_fraction(),
+            ),
+        };
     }
 }
@@ -0,0 +1,125 @@
+use alloy::{
+    consensus::{TxEip4844WithSidecar, Typed2718, transaction::RlpEcdsaEncodableTx},
+    eips::eip4844::{Blob, c_kzg, env_settings::EnvKzgSettings},
+    network::TxSignerSync,
+    primitives::FixedBytes,
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
+};
+use alloy_rlp::{Encodable, Header};
+use rand::{Rng, rngs::StdRng};
+
+/// The version of the sidecar wrapper carrying cell proofs, as of EIP-7594
+pub const WRAPPER_VERSION: u8 = 1;
+
+/// The number of cells of an extended blob, and so of cell proofs per blob
+pub const CELLS_PER_BLOB: usize = 128;
+
+/// Computes the cell proofs of the given blobs, `CELLS_PER_BLOB` per blob.
+/// Blobs that are not valid get random proofs instead.
+pub fn cell_proofs(blobs: &[Blob], random: &mut StdRng) -> Vec<FixedBytes<48>> {
+    let settings = EnvKzgSettings::Default.get();
+    let mut proofs = vec![];
+
+    for blob in blobs {
+        match c_kzg::Blob::from_bytes(blob.as_slice())
+            .and_then(|blob| settings.compute_cells_and_kzg_proofs(&blob))
+        {
+            Ok((_, cell_proofs)) => proofs.extend(
+                cell_proofs.iter().map(|proof| FixedBytes::new(proof.to_bytes().into_inner())),
+            ),
+            Err(_) => proofs
+                .extend((0..CELLS_PER_BLOB).map(|_| FixedBytes::new(random.random::<[u8; 48]>()))),
+        }
+    }
+
+    proofs
+}
+
+/// Signs a blob transaction and encodes it in the network form of EIP-7594,
+/// where the sidecar carries a version byte and the cell proofs of each blob
+/// instead of one proof per blob. 30% of the time, the sidecar is broken in
+/// a way that only shows up around the fork transition: a wrong version,
+/// a wrong number of proofs, or proofs of one format under the other one.
+///
+/// # Arguments
+///
+/// * `tx` - The blob transaction to sign, with its sidecar.
+/// * `sk` - The signing key of the runner.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The signed envelope, or empty bytes if the transaction cannot be signed.
+pub fn envelope(tx: TxEip4844WithSidecar, sk: &SigningKey, random: &mut StdRng) -> Vec<u8> {
+    let TxEip4844WithSidecar { tx: mut inner, sidecar } = tx;
+    let signer = PrivateKeySigner::from_signing_key(sk.clone());
+    let Ok(signature) = signer.sign_transaction_sync(&mut inner) else {
+        return vec![];
+    };
+
+    let mut version = Some(WRAPPER_VERSION);
+    let mut proofs = cell_proofs(&sidecar.blobs, random);
+
+    if random.random_bool(0.3) {
+        match random.random_range(0..5) {
+            // A wrapper version other than the one of EIP-7594
+            0 => {
+                version = Some(if random.random_bool(0.5) {
+                    0
+                } else {
+                    random.random_range(WRAPPER_VERSION + 1..=u8::MAX)
+                })
+            }
+            // Too few or too many cell proofs
+            1 => {
+                if random.random_bool(0.5) && !proofs.is_empty() {
+                    proofs.truncate(random.random_range(0..proofs.len()));
+                } else {
+                    let extra = random.random_range(1..=CELLS_PER_BLOB);
+                    proofs.extend((0..extra).map(|_| FixedBytes::new(random.random::<[u8; 48]>())));
+                }
+            }
+            // The blob proofs of EIP-4844 under the version of EIP-7594
+            2 => proofs = sidecar.proofs.clone(),
+            // The cell proofs without the version byte, as in EIP-4844
+            3 => version = None,
+            // Two cell proofs swapped
+            _ => {
+                if !proofs.is_empty() {
+                    let a = random.random_range(0..proofs.len());
+                    let b = random.random_range(0..proofs.len());
+                    proofs.swap(a, b);
+                }
+            }
+        }
+    }
+
+    let mut payload = vec![];
+    inner.rlp_encode_signed(&signature, &mut payload);
+    if let Some(version) = version {
+        version.encode(&mut payload);
+    }
+    sidecar.blobs.encode(&mut payload);
+    sidecar.commitments.encode(&mut payload);
+    proofs.encode(&mut payload);
+
+    let mut out = vec![inner.ty()];
+    Header { list: true, payload_length: payload.len() }.encode(&mut out);
+    out.extend_from_slice(&payload);
+    out
+}
+
+#[test]
+fn test_cell_proofs() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let blobs = vec![
+        Blob::ZERO,
+        Blob::repeat_byte(0xff),
+    ];
+    let proofs = cell_proofs(&blobs, &mut random);
+
+    assert_eq!(proofs.len(), 2 * CELLS_PER_BLOB);
+    assert!(proofs[..CELLS_PER_BLOB].iter().all(|proof| proof[0] & 0x80 != 0));
+}
@@ -0,0 +1,136 @@
+use crate::{
+    cache::BuilderCache,
+    nonces::NonceManager,
+    scenario::{PlannedTx, Scenario},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// Checkpoint is the state of a runner at a given iteration. It is written to
+/// disk so that a campaign can continue from exactly where it stopped, even
+/// after restarting rakoon. The signing key and RPC URL are not part of it, as
+/// they are provided by the app when the runner is restored.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The name of the runner the checkpoint was taken from
+    pub runner: String,
+    /// The seed the runner's random number generator is re-created with
+    pub seed: u64,
+    /// The transaction being mutated
+    pub current_tx: Vec<u8>,
+    /// The cached values used to build transactions
+    pub cache: BuilderCache,
+    /// The maximum number of operations per mutation of the mutator
+    pub max_operations_per_mutation: u64,
+    /// The seed of the mutator
+    pub mutator_seed: u64,
+    /// The number of crashes found so far
+    pub crash_counter: u64,
+    /// The number of transactions sent so far
+    pub tx_counter: u64,
+    /// The mutated transactions waiting to be packed into a batch
+    #[serde(default)]
+    pub pending_batch: Vec<Vec<u8>>,
+    /// The index in the sender pool of the account the runner sends from,
+    /// if it sends from the pool
+    #[serde(default)]
+    pub sender: Option<usize>,
+    /// The nonces tracked for each sender and the fees last sent with them
+    #[serde(default)]
+    pub nonces: NonceManager,
+    /// The index of the next scenario of the scenario runner
+    #[serde(default)]
+    pub scenario: usize,
+    /// The scenario that was running, if any
+    #[serde(default)]
+    pub running_scenario: Option<Scenario>,
+    /// The transactions the running scenario had left to send
+    #[serde(default)]
+    pub planned: Vec<PlannedTx>,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoints of every worker of a runner instance to the given
+    /// file as JSON
+    pub fn save(checkpoints: &[Checkpoint], path: &Path) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(checkpoints).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    /// Reads the checkpoints of every worker of a runner instance from the
+    /// given file
+    pub fn load(path: &Path) -> io::Result<Vec<Checkpoint>> {
+        let data = fs::read(path)?;
+        serde_json::from_slice(&data).map_err(io::Error::other)
+    }
+}
+
+#[test]
+fn test_checkpoint_roundtrip() {
+    use crate::nonces::{Fees, NoncePattern};
+    use alloy::primitives::Address;
+
+    let fees = Fees { gas_price: 11, ..Default::default() };
+    let mut nonces = NonceManager::default();
+    nonces.track(Address::ZERO, 5);
+    nonces.record(Address::ZERO, 5, NoncePattern::Sequential, fees);
+
+    let checkpoint = Checkpoint {
+        runner: "legacy".to_string(),
+        seed: 1,
+        current_tx: vec![
+            0xde, 0xad, 0xbe, 0xef,
+        ],
+        cache: BuilderCache {
+            gas_price: 1,
+            max_priority_fee: 2,
+            max_fee_per_blob_gas: 3,
+            balance: alloy::primitives::U256::from(4),
+            nonce: 5,
+            chain_id: 6,
+            block_gas_limit: 7,
+            base_fee: 8,
+            excess_blob_gas: 9,
+            blob_base_fee: 10,
+            fork: crate::fork::Fork::Osaka,
+            contracts: vec![],
+            ..Default::default()
+        },
+        max_operations_per_mutation: 1000,
+        mutator_seed: 7,
+        crash_counter: 8,
+        tx_counter: 9,
+        pending_batch: vec![
+            vec![
+                0xca, 0xfe,
+            ],
+        ],
+        sender: Some(3),
+        nonces,
+        scenario: 2,
+        running_scenario: Some(Scenario::Replacement),
+        planned: vec![PlannedTx { sender: 1, nonce: 2, fees, blob: false }],
+    };
+
+    let path = std::env::temp_dir().join("rakoon_checkpoint_roundtrip.json");
+    Checkpoint::save(std::slice::from_ref(&checkpoint), &path).unwrap();
+    let mut restored = Checkpoint::load(&path).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(restored.len(), 1);
+    let restored = restored.remove(0);
+
+    assert_eq!(restored.runner, checkpoint.runner);
+    assert_eq!(restored.seed, checkpoint.seed);
+    assert_eq!(restored.current_tx, checkpoint.current_tx);
+    assert_eq!(restored.cache.nonce, checkpoint.cache.nonce);
+    assert_eq!(restored.cache.balance, checkpoint.cache.balance);
+    assert_eq!(restored.mutator_seed, checkpoint.mutator_seed);
+    assert_eq!(restored.tx_counter, checkpoint.tx_counter);
+    assert_eq!(restored.pending_batch, checkpoint.pending_batch);
+    assert_eq!(restored.sender, checkpoint.sender);
+    assert_eq!(restored.nonces.fees(Address::ZERO, 5), Some(fees));
+    assert_eq!(restored.scenario, checkpoint.scenario);
+    assert_eq!(restored.running_scenario, checkpoint.running_scenario);
+    assert_eq!(restored.planned, checkpoint.planned);
+}
@@ -0,0 +1,242 @@
+use crate::{
+    fork::Fork,
+    methods::{METHODS, SEND},
+    nonces::NonceMix,
+    scenario::{SCENARIOS, Scenario},
+    signing::{BlobForm, Signing},
+};
+use alloy::transports::http::reqwest::Url;
+use common::constants::STATIC_KEYS;
+use rand::{Rng, rngs::StdRng};
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+/// RunnerConfig holds the settings that tune how a runner sends its requests.
+/// They can be set globally or per runner instance from the terminal.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RunnerConfig {
+    /// The maximum number of requests waiting for a response at the same time
+    pub in_flight: usize,
+    /// The maximum number of requests sent per second, or 0 for no limit
+    pub max_tps: u64,
+    /// The number of raw transactions packed into a single JSON-RPC batch, or
+    /// 0 to send them one by one
+    pub batch: usize,
+    /// The methods generated and mutated transactions are routed to, one of
+    /// them picked at random for each request
+    pub methods: Vec<&'static str>,
+    /// The authenticated Engine API endpoint, used by the engine runner
+    pub engine_url: Url,
+    /// The file holding the hex-encoded JWT secret of the Engine API
+    pub jwt_secret: PathBuf,
+    /// What is done with the signature of mutated transactions
+    pub signing: Signing,
+    /// The envelope form blob transactions are encoded in
+    pub blob_form: BlobForm,
+    /// Whether legacy transactions are signed with replay protection
+    pub eip155: bool,
+    /// The number of accounts of `STATIC_KEYS` generated transactions are
+    /// spread across, or 0 to send them all from the key of the runner
+    pub senders: usize,
+    /// The first account of `STATIC_KEYS` the senders of a worker start at.
+    /// It is not a parameter, but set by the app for each worker, so that
+    /// workers and instances running at the same time have their own
+    /// accounts and do not race on their nonces
+    pub sender_offset: usize,
+    /// The weights of the nonce patterns of generated transactions
+    pub nonces: NonceMix,
+    /// The scenarios the scenario runner goes through, in order
+    pub scenarios: Vec<Scenario>,
+    /// The Prometheus endpoint of the node, which the scenario runner reads
+    /// its memory from
+    pub metrics_url: Option<Url>,
+    /// The chance, in percent, that calls target one of the fixture
+    /// contracts, which are not deployed at all if it is 0
+    pub fixtures: u32,
+    /// The chance, in percent, that gas limits are picked around the
+    /// estimate of the node, the intrinsic gas and the gas caps
+    pub estimate: u32,
+    /// The fork transactions are generated for, or `None` to detect it from
+    /// the node when the runner starts
+    pub fork: Option<Fork>,
+    /// The chance, in percent, that each part of a transaction follows the
+    /// rules of the fork before or after instead
+    pub boundary: u32,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            in_flight: 1,
+            max_tps: 0,
+            batch: 0,
+            methods: vec![SEND],
+            engine_url: Url::parse("http://localhost:8551").unwrap(),
+            jwt_secret: PathBuf::from("jwt.hex"),
+            signing: Signing::None,
+            blob_form: BlobForm::Network,
+            eip155: true,
+            senders: 0,
+            sender_offset: 0,
+            nonces: NonceMix::default(),
+            scenarios: SCENARIOS.iter().map(|s| s.parse().unwrap()).collect(),
+            metrics_url: None,
+            fixtures: 25,
+            estimate: 20,
+            fork: None,
+            boundary: 0,
+        }
+    }
+}
+
+impl RunnerConfig {
+    /// The names of the parameters that can be set from the terminal
+    pub const PARAMS: [&str; 17] = [
+        "inflight",
+        "max_tps",
+        "batch",
+        "methods",
+        "engine",
+        "jwt",
+        "signing",
+        "blob_form",
+        "eip155",
+        "senders",
+        "nonces",
+        "scenarios",
+        "metrics",
+        "fixtures",
+        "estimate",
+        "fork",
+        "boundary",
+    ];
+
+    /// Sets a parameter given its name and its value as typed in the terminal
+    pub fn set(&mut self, param: &str, value: &str) -> Result<(), String> {
+        match param {
+            "inflight" => match value.parse::<usize>() {
+                Ok(in_flight) if in_flight > 0 => self.in_flight = in_flight,
+                _ => return Err(format!("invalid inflight: {}", value)),
+            },
+            "max_tps" => match value.parse::<u64>() {
+                Ok(max_tps) => self.max_tps = max_tps,
+                _ => return Err(format!("invalid max_tps: {}", value)),
+            },
+            "batch" => match value.parse::<usize>() {
+                Ok(batch) => self.batch = batch,
+                _ => return Err(format!("invalid batch: {}", value)),
+            },
+            "methods" => {
+                let mut methods = vec![];
+                for method in value.split(',') {
+                    match METHODS.iter().find(|m| **m == method) {
+                        Some(method) if !methods.contains(method) => methods.push(*method),
+                        Some(_) => {}
+                        None => return Err(format!("invalid method: {}", method)),
+                    }
+                }
+                self.methods = methods;
+            }
+            "engine" => match Url::parse(value) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                    self.engine_url = url
+                }
+                _ => return Err(format!("invalid engine url: {}", value)),
+            },
+            "jwt" => self.jwt_secret = PathBuf::from(value),
+            "signing" => self.signing = value.parse()?,
+            "blob_form" => self.blob_form = value.parse()?,
+            "eip155" => match value.parse::<bool>() {
+                Ok(eip155) => self.eip155 = eip155,
+                _ => return Err(format!("invalid eip155: {}", value)),
+            },
+            "senders" => match value.parse::<usize>() {
+                Ok(senders) if senders <= STATIC_KEYS.len() => self.senders = senders,
+                _ => return Err(format!("invalid senders: {}", value)),
+            },
+            "nonces" => self.nonces = value.parse()?,
+            "scenarios" => {
+                let mut scenarios = vec![];
+                for scenario in value.split(',') {
+                    scenarios.push(scenario.parse()?);
+                }
+                self.scenarios = scenarios;
+            }
+            "metrics" => match Url::parse(value) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                    self.metrics_url = Some(url)
+                }
+                _ if value == "none" => self.metrics_url = None,
+                _ => return Err(format!("invalid metrics url: {}", value)),
+            },
+            "fixtures" => match value.parse::<u32>() {
+                Ok(fixtures) if fixtures <= 100 => self.fixtures = fixtures,
+                _ => return Err(format!("invalid fixtures: {}", value)),
+            },
+            "estimate" => match value.parse::<u32>() {
+                Ok(estimate) if estimate <= 100 => self.estimate = estimate,
+                _ => return Err(format!("invalid estimate: {}", value)),
+            },
+            "fork" => match value {
+                "auto" => self.fork = None,
+                fork => self.fork = Some(fork.parse()?),
+            },
+            "boundary" => match value.parse::<u32>() {
+                Ok(boundary) if boundary <= 100 => self.boundary = boundary,
+                _ => return Err(format!("invalid boundary: {}", value)),
+            },
+            _ => return Err(format!("invalid parameter: {}", param)),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of a parameter given its name, as typed in the terminal
+    pub fn get(&self, param: &str) -> Option<String> {
+        match param {
+            "inflight" => Some(self.in_flight.to_string()),
+            "max_tps" => Some(self.max_tps.to_string()),
+            "batch" => Some(self.batch.to_string()),
+            "methods" => Some(self.methods.join(",")),
+            "engine" => Some(self.engine_url.to_string()),
+            "jwt" => Some(self.jwt_secret.display().to_string()),
+            "signing" => Some(self.signing.to_string()),
+            "blob_form" => Some(self.blob_form.to_string()),
+            "eip155" => Some(self.eip155.to_string()),
+            "senders" => Some(self.senders.to_string()),
+            "nonces" => Some(self.nonces.to_string()),
+            "scenarios" => {
+                Some(self.scenarios.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(","))
+            }
+            "metrics" => {
+                Some(self.metrics_url.as_ref().map_or("none".to_string(), |url| url.to_string()))
+            }
+            "fixtures" => Some(self.fixtures.to_string()),
+            "estimate" => Some(self.estimate.to_string()),
+            "fork" => Some(self.fork.map_or("auto".to_string(), |fork| fork.to_string())),
+            "boundary" => Some(self.boundary.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Picks the method the next request is routed to
+    pub fn method(&self, random: &mut StdRng) -> &'static str {
+        self.methods[random.random_range(0..self.methods.len())]
+    }
+}
+
+impl Display for RunnerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "inflight={}, max_tps={}, batch={}, methods={}, signing={}",
+            self.in_flight,
+            self.max_tps,
+            self.batch,
+            self.methods.join(","),
+            self.signing
+        )
+    }
+}
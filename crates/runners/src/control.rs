@@ -0,0 +1,324 @@
+use crate::{
+    Runner,
+    checkpoint::Checkpoint,
+    config::RunnerConfig,
+    logger::Logger,
+    pipeline::{Pipeline, Response},
+};
+use mutator::Mutator;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::time::Instant;
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    oneshot,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Commands the app can send to a runner while it is running
+pub enum Control {
+    /// Suspends the runner loop in place
+    Pause,
+    /// Resumes a paused runner loop
+    Resume,
+    /// Takes a checkpoint of the runner and replies with it
+    Checkpoint(oneshot::Sender<Checkpoint>),
+}
+
+/// What the runner loop has to do next
+pub enum Step {
+    /// Run one more iteration
+    Run,
+    /// Take a checkpoint and reply with it
+    Checkpoint(oneshot::Sender<Checkpoint>),
+    /// Exit the loop
+    Stop,
+}
+
+/// Controller is the runner side of the channel the app uses to stop, pause,
+/// resume and checkpoint a runner. It is only checked between iterations, so
+/// that a runner never leaves a transaction half-sent.
+pub struct Controller {
+    /// The token the app cancels to stop the runner
+    token: CancellationToken,
+    /// The commands sent by the app
+    receiver: UnboundedReceiver<Control>,
+    /// Whether the runner is paused or not
+    paused: bool,
+}
+
+impl Controller {
+    /// Creates a new `Controller` stopped by the given `token`, together with
+    /// the sender the app uses to pause, resume and checkpoint the runner.
+    pub fn new(token: CancellationToken) -> (Self, UnboundedSender<Control>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self { token, receiver, paused: false }, sender)
+    }
+
+    /// Runs a future until it completes or the runner is stopped, for the
+    /// setup of a runner, which may wait on the node for a while
+    ///
+    /// # Returns
+    ///
+    /// The output of the future, or `None` if the runner was stopped first.
+    pub async fn until_stopped<T>(&self, future: impl Future<Output = T>) -> Option<T> {
+        tokio::select! {
+            _ = self.token.cancelled() => None,
+            output = future => Some(output),
+        }
+    }
+
+    /// Handles the commands sent by the app since the last iteration. While the
+    /// runner is paused, this waits until it is resumed, stopped or asked for a
+    /// checkpoint.
+    pub async fn next(&mut self) -> Step {
+        loop {
+            if self.token.is_cancelled() {
+                return Step::Stop;
+            }
+
+            let control = if self.paused {
+                tokio::select! {
+                    _ = self.token.cancelled() => return Step::Stop,
+                    control = self.receiver.recv() => control,
+                }
+            } else {
+                match self.receiver.try_recv() {
+                    Ok(control) => Some(control),
+                    Err(_) => return Step::Run,
+                }
+            };
+
+            match control {
+                Some(Control::Pause) => self.paused = true,
+                Some(Control::Resume) => self.paused = false,
+                Some(Control::Checkpoint(reply)) => return Step::Checkpoint(reply),
+                // The app is gone, so nobody can resume the runner anymore
+                None => return Step::Stop,
+            }
+        }
+    }
+}
+
+/// Session is the state every runner keeps, whatever it sends: the payload it
+/// mutates, its counters, its logger and its pipeline
+pub struct Session {
+    pub seed: u64,
+    pub current_tx: Vec<u8>,
+    pub mutator: Mutator,
+    pub running: bool,
+    pub crash_counter: u64,
+    pub tx_counter: u64,
+    pub logger: Logger,
+    pub pipeline: Pipeline,
+}
+
+impl Session {
+    /// Creates a new `Session` logging under the given name, with the
+    /// in-flight window and rate limit of the config
+    pub fn new(
+        name: &str,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        config: &RunnerConfig,
+    ) -> Self {
+        Self {
+            seed,
+            current_tx: vec![],
+            mutator: Mutator::new(max_operations_per_mutation, seed),
+            running: false,
+            crash_counter: 0,
+            tx_counter: 0,
+            logger: Logger::new(name).unwrap(),
+            pipeline: Pipeline::new(config.in_flight, config.max_tps),
+        }
+    }
+
+    /// Checks the responses received against the payload they answer, and
+    /// counts a crash if the node went down
+    pub fn check(&mut self, responses: Vec<Response>) -> bool {
+        let crashed = self.logger.check_responses(responses, self.pipeline.in_flight());
+        if crashed {
+            self.crash_counter += 1;
+        }
+        crashed
+    }
+}
+
+/// What a single iteration of a runner did
+pub enum Outcome {
+    /// A request was submitted to the pipeline
+    Sent,
+    /// Nothing was sent
+    Idle,
+    /// The node crashed
+    Crashed,
+}
+
+/// Worker is a runner driven by the shared loop of `run`, which checks the
+/// commands of the app and the responses of the node between iterations. The
+/// runner only supplies what a single iteration sends.
+#[allow(async_fn_in_trait)]
+pub trait Worker {
+    /// The type of the runner, written in its checkpoints
+    const RUNNER: Runner;
+
+    fn session(&self) -> &Session;
+    fn session_mut(&mut self) -> &mut Session;
+
+    /// Builds or mutates the next payload and submits it to the pipeline
+    async fn step(&mut self, random: &mut StdRng) -> Outcome;
+
+    /// Called once the loop is over and every request has been answered
+    async fn finish(&mut self) {}
+
+    /// Writes the state of the runner that is not part of its session to a
+    /// checkpoint
+    fn save(&self, _checkpoint: &mut Checkpoint) {}
+
+    /// Restores the state of the runner that is not part of its session from
+    /// a checkpoint
+    fn load(&mut self, _checkpoint: &mut Checkpoint) {}
+
+    /// Runs the runner until it is stopped or the node crashes, and writes the
+    /// corpus and the summary of the session once it exits
+    async fn run(&mut self, mut controller: Controller) {
+        let mut random = StdRng::seed_from_u64(self.session().seed);
+        let started_at = Instant::now();
+        self.session_mut().running = true;
+
+        loop {
+            match controller.next().await {
+                Step::Run => {}
+                Step::Checkpoint(reply) => {
+                    let _ = reply.send(self.checkpoint(&mut random));
+                    continue;
+                }
+                Step::Stop => break,
+            }
+
+            match self.step(&mut random).await {
+                Outcome::Sent => self.session_mut().tx_counter += 1,
+                Outcome::Idle => continue,
+                Outcome::Crashed => break,
+            }
+
+            // The node may have crashed with any of the requests in flight, so
+            // the responses are checked against the payload they answer
+            let session = self.session_mut();
+            let responses = session.pipeline.completed();
+            if session.check(responses) {
+                session.pipeline.abort();
+                break;
+            }
+        }
+
+        // Wait for the requests still in flight before exiting
+        let session = self.session_mut();
+        let responses = session.pipeline.drain().await;
+        session.check(responses);
+        self.finish().await;
+
+        let session = self.session_mut();
+        session.running = false;
+        let _ = session.logger.save_corpus(&session.current_tx);
+        let _ = session.logger.generate_session_summary(
+            started_at.elapsed(),
+            session.tx_counter,
+            session.crash_counter,
+        );
+        let _ = session.logger.flush();
+    }
+
+    /// Takes a checkpoint of the runner. The random number generator is
+    /// re-seeded from itself, so that a runner restored from the checkpoint
+    /// continues with exactly the same sequence.
+    fn checkpoint(&self, random: &mut StdRng) -> Checkpoint {
+        let seed = random.random::<u64>();
+        *random = StdRng::seed_from_u64(seed);
+
+        let session = self.session();
+        let mut checkpoint = Checkpoint {
+            runner: Self::RUNNER.to_string(),
+            seed,
+            current_tx: session.current_tx.clone(),
+            cache: Default::default(),
+            max_operations_per_mutation: session.mutator.max_operations_per_mutation(),
+            mutator_seed: session.mutator.seed(),
+            crash_counter: session.crash_counter,
+            tx_counter: session.tx_counter,
+            pending_batch: vec![],
+            sender: None,
+            nonces: Default::default(),
+            scenario: 0,
+            running_scenario: None,
+            planned: vec![],
+        };
+        self.save(&mut checkpoint);
+        checkpoint
+    }
+
+    /// Restores the state of the runner from a checkpoint
+    fn restore(&mut self, mut checkpoint: Checkpoint) {
+        self.load(&mut checkpoint);
+
+        let session = self.session_mut();
+        session.seed = checkpoint.seed;
+        session.current_tx = checkpoint.current_tx;
+        session.mutator =
+            Mutator::new(checkpoint.max_operations_per_mutation, checkpoint.mutator_seed);
+        session.crash_counter = checkpoint.crash_counter;
+        session.tx_counter = checkpoint.tx_counter;
+    }
+}
+
+#[tokio::test]
+async fn test_worker_restore() {
+    // A worker whose payloads only depend on its random number generator and
+    // mutator, so that restoring it needs no node
+    struct Replay {
+        session: Session,
+    }
+
+    impl Worker for Replay {
+        const RUNNER: Runner = Runner::Random;
+
+        fn session(&self) -> &Session {
+            &self.session
+        }
+
+        fn session_mut(&mut self) -> &mut Session {
+            &mut self.session
+        }
+
+        async fn step(&mut self, random: &mut StdRng) -> Outcome {
+            if random.random_bool(0.1) || self.session.current_tx.is_empty() {
+                self.session.current_tx = random.random::<[u8; 32]>().to_vec();
+            } else {
+                self.session.mutator.mutate(&mut self.session.current_tx);
+            }
+            Outcome::Sent
+        }
+    }
+
+    let config = RunnerConfig::default();
+    let mut worker = Replay { session: Session::new("restore-test", 1, 1000, &config) };
+    let mut random = StdRng::seed_from_u64(worker.session.seed);
+    for _ in 0..16 {
+        worker.step(&mut random).await;
+    }
+
+    let path = std::env::temp_dir().join("rakoon_worker_restore.json");
+    Checkpoint::save(&[worker.checkpoint(&mut random)], &path).unwrap();
+    let checkpoint = Checkpoint::load(&path).unwrap().remove(0);
+    let _ = std::fs::remove_file(&path);
+
+    // A worker started from another seed continues exactly as the original
+    let mut restored = Replay { session: Session::new("restore-test", 2, 10, &config) };
+    restored.restore(checkpoint);
+    let mut restored_random = StdRng::seed_from_u64(restored.session.seed);
+    for _ in 0..16 {
+        worker.step(&mut random).await;
+        restored.step(&mut restored_random).await;
+        assert_eq!(restored.session.current_tx, worker.session.current_tx);
+    }
+}
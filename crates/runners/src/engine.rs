@@ -0,0 +1,478 @@
+use crate::{
+    Runner,
+    builder::Builder,
+    cache::BuilderCache,
+    checkpoint::Checkpoint,
+    config::RunnerConfig,
+    control::{Outcome, Session, Worker},
+    fixtures,
+    fork::Fork,
+    jwt::JwtSecret,
+};
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope, TxLegacy},
+    eips::{eip2718::Encodable2718, eip4895::Withdrawal, eip7685::Requests},
+    network::TxSignerSync,
+    primitives::{Address, B256, Bloom, Bytes, U256},
+    providers::Provider,
+    rpc::types::{
+        BlockId, TransactionInput, TransactionRequest,
+        engine::{ExecutionPayloadV1, ExecutionPayloadV2, ExecutionPayloadV3, ForkchoiceState},
+    },
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
+    transports::{
+        TransportErrorKind, TransportResult,
+        http::reqwest::{Client, Url},
+    },
+};
+use common::{
+    connect,
+    constants::{MAX_PAYLOAD_TRANSACTIONS, MAX_WITHDRAWALS},
+    types::Backend,
+};
+use rand::{Rng, rngs::StdRng, seq::SliceRandom};
+use serde_json::{Value, json};
+use std::mem;
+
+/// EngineRunner speaks the Engine API on the authenticated port of the node,
+/// sending `engine_newPayloadV3/V4` and `engine_forkchoiceUpdatedV3` calls
+/// with payloads built from mutated transaction lists, invalid blob hashes,
+/// bad withdrawals and malformed execution requests.
+pub struct EngineRunner {
+    pub session: Session,
+    pub sk: SigningKey,
+    pub provider: Backend,
+    pub client: Client,
+    pub jwt_secret: JwtSecret,
+    pub cache: BuilderCache,
+    pub sender: Address,
+    pub config: RunnerConfig,
+    pub last_block_hash: B256,
+}
+
+impl Builder for EngineRunner {
+    fn provider(&self) -> &Backend {
+        &self.provider
+    }
+
+    fn cache(&self) -> &BuilderCache {
+        &self.cache
+    }
+
+    fn cache_mut(&mut self) -> &mut BuilderCache {
+        &mut self.cache
+    }
+
+    fn sender(&self) -> Address {
+        self.sender
+    }
+
+    fn config(&self) -> &RunnerConfig {
+        &self.config
+    }
+}
+
+impl Worker for EngineRunner {
+    const RUNNER: Runner = Runner::Engine;
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    async fn step(&mut self, random: &mut StdRng) -> Outcome {
+        // 50% chance to update the cache
+        if random.random_bool(0.5) {
+            self.cache.update(&self.provider, self.sender).await;
+        }
+
+        let (method, params) = self.create_engine_call(random).await;
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.session.tx_counter,
+            "method": method,
+            "params": params,
+        });
+        self.session.current_tx = serde_json::to_vec(&body).unwrap_or_default();
+
+        let token = self.token(random);
+        let request = send(
+            self.client.clone(),
+            self.config.engine_url.clone(),
+            token,
+            self.session.current_tx.clone(),
+        );
+        self.session.pipeline.submit(self.session.current_tx.clone(), request).await;
+
+        Outcome::Sent
+    }
+
+    fn save(&self, checkpoint: &mut Checkpoint) {
+        checkpoint.cache = self.cache.clone();
+    }
+
+    fn load(&mut self, checkpoint: &mut Checkpoint) {
+        self.cache = mem::take(&mut checkpoint.cache);
+    }
+}
+
+impl EngineRunner {
+    pub async fn new(
+        name: &str,
+        rpc_url: Url,
+        sk: SigningKey,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        config: RunnerConfig,
+    ) -> Self {
+        // The transport is chosen by the scheme of the URL. The app checks the
+        // connection before spawning the runner, so this should not fail
+        let provider = connect(&rpc_url, &sk).await.unwrap();
+
+        // The app checks the secret before spawning the runner too
+        let jwt_secret = JwtSecret::from_file(&config.jwt_secret).unwrap();
+
+        let sender = Address::from_private_key(&sk);
+        let contracts = fixtures::deploy(&provider, &rpc_url, sender, &config).await;
+        let account = provider.get_account(sender).await.unwrap_or_default();
+        let mut cache = BuilderCache {
+            gas_price: provider.get_gas_price().await.unwrap_or_default(),
+            max_priority_fee: provider.get_max_priority_fee_per_gas().await.unwrap_or_default(),
+            max_fee_per_blob_gas: provider.get_blob_base_fee().await.unwrap_or_default(),
+            balance: account.balance,
+            nonce: account.nonce,
+            chain_id: provider.get_chain_id().await.unwrap_or_default(),
+            contracts,
+            ..Default::default()
+        };
+        cache.update_block(&provider).await;
+        cache.fork = Fork::resolve(config.fork, &provider).await;
+
+        let session = Session::new(name, seed, max_operations_per_mutation, &config);
+
+        Self {
+            session,
+            sk,
+            provider,
+            client: Client::new(),
+            jwt_secret,
+            cache,
+            sender,
+            config,
+            last_block_hash: B256::ZERO,
+        }
+    }
+
+    /// Creates an Engine API call, either a new payload on top of the latest
+    /// block of the node or a forkchoice update
+    pub async fn create_engine_call(&mut self, random: &mut StdRng) -> (&'static str, Value) {
+        match random.random_range(0..3) {
+            0 => ("engine_newPayloadV3", self.new_payload(false, random).await),
+            1 => ("engine_newPayloadV4", self.new_payload(true, random).await),
+            _ => ("engine_forkchoiceUpdatedV3", self.forkchoice_updated(random).await),
+        }
+    }
+
+    // Helper function to create the params of a new payload call. The payload
+    // is built on top of the latest block, so that it gets as far as possible
+    // in the validation of the node.
+    async fn new_payload(&mut self, with_requests: bool, random: &mut StdRng) -> Value {
+        let parent = self.provider.get_block(BlockId::latest()).await.ok().flatten();
+        let (parent_hash, number, timestamp, gas_limit, base_fee, excess_blob_gas, state_root) =
+            match &parent {
+                Some(block) => (
+                    block.header.hash,
+                    block.header.number,
+                    block.header.timestamp,
+                    block.header.gas_limit,
+                    block.header.base_fee_per_gas.unwrap_or_default(),
+                    block.header.excess_blob_gas.unwrap_or_default(),
+                    block.header.state_root,
+                ),
+                None => (B256::ZERO, 0, 0, 30_000_000, 0, 0, B256::ZERO),
+            };
+
+        let mut transactions = vec![];
+        for _ in 0..random.random_range(0..=MAX_PAYLOAD_TRANSACTIONS) {
+            let mut tx = self.payload_transaction(random).await;
+            // 30% chance to mutate the transaction
+            if random.random_bool(0.3) {
+                self.session.mutator.mutate(&mut tx);
+            }
+            transactions.push(Bytes::from(tx));
+        }
+
+        let mut payload = ExecutionPayloadV3 {
+            payload_inner: ExecutionPayloadV2 {
+                payload_inner: ExecutionPayloadV1 {
+                    parent_hash: if random.random_bool(0.9) {
+                        parent_hash
+                    } else {
+                        self.random_hash(random)
+                    },
+                    fee_recipient: self.random_address(random),
+                    state_root: if random.random_bool(0.5) {
+                        state_root
+                    } else {
+                        self.random_hash(random)
+                    },
+                    receipts_root: self.random_hash(random),
+                    logs_bloom: Bloom::default(),
+                    prev_randao: self.random_hash(random),
+                    block_number: if random.random_bool(0.9) {
+                        number + 1
+                    } else {
+                        random.random()
+                    },
+                    gas_limit: if random.random_bool(0.9) { gas_limit } else { random.random() },
+                    gas_used: random.random_range(0..=gas_limit),
+                    timestamp: if random.random_bool(0.9) {
+                        timestamp + 12
+                    } else {
+                        random.random()
+                    },
+                    // More than 32 bytes of extra data are invalid
+                    extra_data: self.random_bytes(random.random_range(0..=40), random),
+                    base_fee_per_gas: U256::from(base_fee),
+                    block_hash: B256::ZERO,
+                    transactions,
+                },
+                withdrawals: self.withdrawals(random),
+            },
+            blob_gas_used: random.random_range(0..=9) * 131072,
+            excess_blob_gas: if random.random_bool(0.9) {
+                excess_blob_gas
+            } else {
+                random.random()
+            },
+        };
+
+        let versioned_hashes = self.blob_versioned_hashes(random);
+        let parent_beacon_block_root = self.random_hash(random);
+        let execution_requests = self.execution_requests(random);
+
+        // 50% chance to compute the right block hash, as a payload with a wrong
+        // one is rejected before its transactions are executed
+        if random.random_bool(0.5) {
+            if let Ok(mut block) = payload.clone().into_block_raw() {
+                block.header.parent_beacon_block_root = Some(parent_beacon_block_root);
+                if with_requests {
+                    block.header.requests_hash =
+                        Some(Requests::new(execution_requests.clone()).requests_hash());
+                }
+                payload.payload_inner.payload_inner.block_hash = block.header.hash_slow();
+            }
+        } else {
+            payload.payload_inner.payload_inner.block_hash = self.random_hash(random);
+        }
+
+        self.last_block_hash = payload.payload_inner.payload_inner.block_hash;
+
+        if with_requests {
+            json!([
+                payload,
+                versioned_hashes,
+                parent_beacon_block_root,
+                execution_requests
+            ])
+        } else {
+            json!([
+                payload,
+                versioned_hashes,
+                parent_beacon_block_root
+            ])
+        }
+    }
+
+    // Helper function to create the params of a forkchoice update, pointing at
+    // the latest block, the last payload sent or random hashes
+    async fn forkchoice_updated(&mut self, random: &mut StdRng) -> Value {
+        let latest = self
+            .provider
+            .get_block(BlockId::latest())
+            .await
+            .ok()
+            .flatten()
+            .map(|block| block.header.hash)
+            .unwrap_or_default();
+
+        let mut hashes = [
+            latest,
+            self.last_block_hash,
+            B256::ZERO,
+            self.random_hash(random),
+        ];
+        hashes.shuffle(random);
+
+        let state = ForkchoiceState {
+            head_block_hash: if random.random_bool(0.5) { self.last_block_hash } else { hashes[0] },
+            safe_block_hash: hashes[1],
+            finalized_block_hash: hashes[2],
+        };
+
+        // 50% chance to ask the node to build a payload too
+        let attributes = if random.random_bool(0.5) {
+            json!({
+                "timestamp": format!("0x{:x}", random.random::<u64>()),
+                "prevRandao": self.random_hash(random),
+                "suggestedFeeRecipient": self.random_address(random),
+                "withdrawals": self.withdrawals(random),
+                "parentBeaconBlockRoot": self.random_hash(random),
+            })
+        } else {
+            Value::Null
+        };
+
+        json!([
+            state, attributes
+        ])
+    }
+
+    // Helper function to create a signed transaction for the payload out of
+    // the fields of the `Builder`
+    async fn payload_transaction(&self, random: &mut StdRng) -> Vec<u8> {
+        let signer = PrivateKeySigner::from(self.sk.clone());
+        let to = self.to(random);
+        let input = self.input(&to, random).into_input().unwrap_or_default();
+        let request = TransactionRequest {
+            from: Some(self.sender()),
+            to: Some(to),
+            input: TransactionInput::new(input.clone()),
+            ..Default::default()
+        };
+        let gas_limit = self.gas(&request, random).await;
+
+        let envelope = if random.random_bool(0.5) {
+            let mut tx = TxLegacy {
+                chain_id: Some(self.chain_id(random).await),
+                nonce: self.nonce(random).await,
+                gas_price: self.gas_price(random).await,
+                gas_limit,
+                to,
+                value: self.value(random).await,
+                input,
+            };
+            let Ok(signature) = signer.sign_transaction_sync(&mut tx) else {
+                return vec![];
+            };
+            TxEnvelope::from(tx.into_signed(signature))
+        } else {
+            let max_fee_per_gas = self.max_fee_per_gas(random);
+            let mut tx = TxEip1559 {
+                chain_id: self.chain_id(random).await,
+                nonce: self.nonce(random).await,
+                gas_limit,
+                max_fee_per_gas,
+                max_priority_fee_per_gas: self
+                    .max_priority_fee_per_gas(max_fee_per_gas, random)
+                    .await,
+                to,
+                value: self.value(random).await,
+                access_list: self.access_list(random),
+                input,
+            };
+            let Ok(signature) = signer.sign_transaction_sync(&mut tx) else {
+                return vec![];
+            };
+            TxEnvelope::from(tx.into_signed(signature))
+        };
+
+        envelope.encoded_2718()
+    }
+
+    // Helper function to create withdrawals, with repeated indices and huge
+    // amounts from time to time
+    fn withdrawals(&self, random: &mut StdRng) -> Vec<Withdrawal> {
+        let mut withdrawals = vec![];
+        let first_index = random.random::<u32>() as u64;
+
+        for i in 0..random.random_range(0..=MAX_WITHDRAWALS) {
+            withdrawals.push(Withdrawal {
+                index: if random.random_bool(0.9) { first_index + i as u64 } else { first_index },
+                validator_index: random.random_range(0..1_000_000),
+                address: self.random_address(random),
+                amount: if random.random_bool(0.9) {
+                    random.random_range(0..32_000_000_000)
+                } else {
+                    u64::MAX
+                },
+            });
+        }
+
+        withdrawals
+    }
+
+    // Helper function to create execution requests, with unknown types, empty
+    // data and types out of order from time to time
+    fn execution_requests(&self, random: &mut StdRng) -> Vec<Bytes> {
+        // Deposits, withdrawals and consolidations, with the size of one request
+        let types: [(u8, usize); 3] = [
+            (0, 192),
+            (1, 76),
+            (2, 116),
+        ];
+        let mut requests = vec![];
+
+        for (request_type, size) in types {
+            if random.random_bool(0.5) {
+                continue;
+            }
+
+            let length = match random.random_range(0..10) {
+                0 => 0,
+                1 => random.random_range(1..size),
+                _ => size * random.random_range(1..4),
+            };
+
+            let mut request = vec![request_type];
+            request.extend_from_slice(&self.random_bytes(length, random));
+            requests.push(Bytes::from(request));
+        }
+
+        // 10% chance to add a request of an unknown type
+        if random.random_bool(0.1) {
+            let mut request = vec![random.random_range(3..=u8::MAX)];
+            request.extend_from_slice(&self.random_bytes(random.random_range(0..256), random));
+            requests.push(Bytes::from(request));
+        }
+
+        // 10% chance to send them out of order
+        if random.random_bool(0.1) {
+            requests.shuffle(random);
+        }
+
+        requests
+    }
+
+    // Helper function to create the JWT for the next request. Most of them are
+    // valid, but some are expired or signed with another secret.
+    fn token(&self, random: &mut StdRng) -> String {
+        match random.random_range(0..20) {
+            0 => self.jwt_secret.token(3600),
+            1 => JwtSecret::random(random).token(0),
+            _ => self.jwt_secret.token(0),
+        }
+    }
+
+    fn random_hash(&self, random: &mut StdRng) -> B256 {
+        B256::from(random.random::<[u8; 32]>())
+    }
+}
+
+/// Sends a request to the Engine API with the given JWT. Failures of the
+/// request are reported as transport errors, so that the crash oracle of the
+/// logger can be reused.
+async fn send(client: Client, url: Url, token: String, body: Vec<u8>) -> TransportResult<()> {
+    client
+        .post(url)
+        .bearer_auth(token)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(TransportErrorKind::custom)
+}
@@ -0,0 +1,306 @@
+use crate::{
+    Runner,
+    config::RunnerConfig,
+    control::{Outcome, Session, Worker},
+};
+use alloy::{
+    hex,
+    signers::k256::ecdsa::SigningKey,
+    transports::{TransportErrorKind, TransportResult, http::reqwest::Url},
+};
+use common::constants::{
+    ENVELOPE_RESPONSE_TIMEOUT, MAX_ENVELOPE_BODY_LENGTH, MAX_TRANSACTION_LENGTH,
+};
+use rand::{Rng, RngCore, rngs::StdRng};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+
+/// EnvelopeRunner fuzzes the layer underneath the JSON-RPC calls. Instead of
+/// sending well-formed requests built by alloy, it writes raw HTTP requests to
+/// the node over TCP, with malformed JSON, odd JSON-RPC fields, giant bodies,
+/// odd content types and chunked encoding.
+pub struct EnvelopeRunner {
+    pub session: Session,
+    pub rpc_url: Url,
+    pub config: RunnerConfig,
+    /// The socket address of the node, which raw requests are written to
+    pub address: String,
+}
+
+impl Worker for EnvelopeRunner {
+    const RUNNER: Runner = Runner::Envelope;
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    async fn step(&mut self, random: &mut StdRng) -> Outcome {
+        // 10% chance to re-generate the request
+        if random.random_bool(0.1) || self.session.current_tx.is_empty() {
+            self.session.current_tx = self.create_request(random);
+        } else {
+            self.session.mutator.mutate(&mut self.session.current_tx);
+        }
+
+        let request = send(self.address.clone(), self.session.current_tx.clone());
+        self.session.pipeline.submit(self.session.current_tx.clone(), request).await;
+
+        Outcome::Sent
+    }
+}
+
+impl EnvelopeRunner {
+    // The key is unused, as the transactions in the envelopes are random bytes
+    pub async fn new(
+        name: &str,
+        rpc_url: Url,
+        _sk: SigningKey,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        config: RunnerConfig,
+    ) -> Self {
+        let session = Session::new(name, seed, max_operations_per_mutation, &config);
+
+        // Raw requests are written straight to the socket of the node
+        let address = format!(
+            "{}:{}",
+            rpc_url.host_str().unwrap_or("localhost"),
+            rpc_url.port_or_known_default().unwrap_or(80)
+        );
+
+        Self { session, rpc_url, config, address }
+    }
+
+    /// Creates a raw HTTP request carrying an `eth_sendRawTransaction` call,
+    /// where both the HTTP framing and the JSON-RPC envelope may be broken
+    pub fn create_request(&self, random: &mut StdRng) -> Vec<u8> {
+        let body = self.body(random);
+
+        let method = match random.random_range(0..20) {
+            0 => "GET",
+            1 => "PUT",
+            2 => "OPTIONS",
+            _ => "POST",
+        };
+
+        let content_type = match random.random_range(0..20) {
+            0 => Some("text/plain"),
+            1 => Some("application/x-www-form-urlencoded"),
+            2 => Some("application/json; charset=utf-16"),
+            3 => Some("multipart/form-data; boundary=rakoon"),
+            4 => Some(";;;"),
+            5 => None,
+            _ => Some("application/json"),
+        };
+
+        let host = self.rpc_url.host_str().unwrap_or("localhost");
+        let mut request =
+            format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, self.rpc_url.path(), host);
+        if let Some(content_type) = content_type {
+            request.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        request.push_str("Connection: close\r\n");
+
+        let mut request = request.into_bytes();
+        match random.random_range(0..10) {
+            // Chunked encoding with chunks of random sizes
+            0 | 1 => {
+                request.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+                // 25% chance to also send a Content-Length, which is ambiguous
+                if random.random_bool(0.25) {
+                    request.extend_from_slice(
+                        format!("Content-Length: {}\r\n", body.len()).as_bytes(),
+                    );
+                }
+                request.extend_from_slice(b"\r\n");
+
+                let mut remaining = body.as_slice();
+                while !remaining.is_empty() {
+                    let size = random.random_range(1..=remaining.len());
+                    let (chunk, rest) = remaining.split_at(size);
+                    request.extend_from_slice(format!("{:x}\r\n", size).as_bytes());
+                    request.extend_from_slice(chunk);
+                    request.extend_from_slice(b"\r\n");
+                    remaining = rest;
+                }
+                request.extend_from_slice(b"0\r\n\r\n");
+            }
+            // Content-Length that does not match the body
+            2 => {
+                let length = body.len() as i64 + random.random_range(-16..=16);
+                request.extend_from_slice(format!("Content-Length: {}\r\n\r\n", length).as_bytes());
+                request.extend_from_slice(&body);
+            }
+            // No Content-Length at all
+            3 => {
+                request.extend_from_slice(b"\r\n");
+                request.extend_from_slice(&body);
+            }
+            _ => {
+                request.extend_from_slice(
+                    format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes(),
+                );
+                request.extend_from_slice(&body);
+            }
+        }
+
+        request
+    }
+
+    // Helper function to create the JSON-RPC body of the request
+    fn body(&self, random: &mut StdRng) -> Vec<u8> {
+        let mut fields = vec![];
+
+        let jsonrpc = match random.random_range(0..20) {
+            0 => Some("\"1.0\""),
+            1 => Some("\"2\""),
+            2 => Some("2.0"),
+            3 => Some("null"),
+            4 => Some("\"\""),
+            5 => None,
+            _ => Some("\"2.0\""),
+        };
+        if let Some(jsonrpc) = jsonrpc {
+            fields.push(format!("\"jsonrpc\":{}", jsonrpc));
+        }
+
+        let id = match random.random_range(0..20) {
+            0 => Some("-1".to_string()),
+            1 => Some(i64::MIN.to_string()),
+            2 => Some(u64::MAX.to_string()),
+            // A number that does not fit in any integer type
+            3 => Some("9".repeat(random.random_range(20..=4096))),
+            4 => Some("1.5".to_string()),
+            5 => Some("1e400".to_string()),
+            6 => Some("\"rakoon\"".to_string()),
+            7 => Some("null".to_string()),
+            8 => Some("[]".to_string()),
+            9 => Some("{}".to_string()),
+            10 => None,
+            _ => Some(random.random_range(0..1000).to_string()),
+        };
+        if let Some(id) = id {
+            fields.push(format!("\"id\":{}", id));
+        }
+
+        let method = match random.random_range(0..20) {
+            0 => "\"\"",
+            1 => "\"ETH_SENDRAWTRANSACTION\"",
+            2 => "null",
+            3 => "42",
+            _ => "\"eth_sendRawTransaction\"",
+        };
+        fields.push(format!("\"method\":{}", method));
+
+        let tx = self.raw_transaction(random);
+        let params = match random.random_range(0..20) {
+            // The bytes as a number instead of a hex string
+            0 => Some(format!("[{}]", random.random::<u64>())),
+            1 => Some(format!("[{{\"data\":\"0x{}\"}}]", tx)),
+            // A hex string with an odd number of digits
+            2 => Some(format!("[\"0x{}0\"]", tx)),
+            // A hex string without the 0x prefix
+            3 => Some(format!("[\"{}\"]", tx)),
+            4 => Some(format!("\"0x{}\"", tx)),
+            5 => Some("[]".to_string()),
+            6 => Some("null".to_string()),
+            7 => Some(format!("[\"0x{}\",\"0x{}\"]", tx, tx)),
+            // A giant hex string
+            8 => Some(format!("[\"0x{}\"]", "00".repeat(self.giant_length(random) / 2))),
+            9 => None,
+            _ => Some(format!("[\"0x{}\"]", tx)),
+        };
+        if let Some(params) = params {
+            fields.push(format!("\"params\":{}", params));
+        }
+
+        // 50% chance to shuffle the fields, as the order should not matter
+        if random.random_bool(0.5) {
+            let (a, b) =
+                (random.random_range(0..fields.len()), random.random_range(0..fields.len()));
+            fields.swap(a, b);
+        }
+
+        let mut body = format!("{{{}}}", fields.join(",")).into_bytes();
+        match random.random_range(0..20) {
+            // Truncated JSON
+            0 => body.truncate(random.random_range(0..body.len())),
+            // Random bytes anywhere in the JSON
+            1 => {
+                let position = random.random_range(0..body.len());
+                body.insert(position, random.random::<u8>());
+            }
+            // Wrapped in a batch
+            2 => {
+                body.insert(0, b'[');
+                body.push(b']');
+            }
+            // A giant amount of whitespace before the JSON
+            3 => body.splice(0..0, vec![b' '; self.giant_length(random)]).for_each(drop),
+            // Trailing garbage after the JSON
+            4 => body.extend_from_slice(b"}]\"\0"),
+            _ => {}
+        }
+
+        body
+    }
+
+    // Helper function to create random transaction bytes as a hex string
+    fn raw_transaction(&self, random: &mut StdRng) -> String {
+        let mut tx = vec![0u8; random.random_range(0..=MAX_TRANSACTION_LENGTH)];
+        random.fill_bytes(&mut tx);
+        hex::encode(tx)
+    }
+
+    // Helper function to get the length of a giant body
+    fn giant_length(&self, random: &mut StdRng) -> usize {
+        random.random_range(MAX_ENVELOPE_BODY_LENGTH / 2..=MAX_ENVELOPE_BODY_LENGTH)
+    }
+}
+
+/// Writes a raw request to the node and waits for the node to answer or close
+/// the connection. Failing to connect is reported as a transport error, so that
+/// the crash oracle of the logger can be reused.
+async fn send(address: String, request: Vec<u8>) -> TransportResult<()> {
+    let mut stream = TcpStream::connect(address).await.map_err(TransportErrorKind::custom)?;
+    stream.write_all(&request).await.map_err(TransportErrorKind::custom)?;
+
+    let mut response = vec![];
+    let _ =
+        timeout(Duration::from_secs(ENVELOPE_RESPONSE_TIMEOUT), stream.read_to_end(&mut response))
+            .await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_envelope_runner() {
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let runner = EnvelopeRunner::new(
+        "envelope",
+        "http://localhost:8545".parse::<Url>().unwrap(),
+        SigningKey::from_slice(
+            &alloy::hex::decode(
+                "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            )
+            .unwrap(),
+        )
+        .unwrap(),
+        1,
+        1000,
+        RunnerConfig::default(),
+    )
+    .await;
+    let request = runner.create_request(&mut rng);
+    println!("request: {:?}", String::from_utf8_lossy(&request));
+}
@@ -0,0 +1,151 @@
+use crate::{bytecode, config::RunnerConfig};
+use alloy::{
+    hex,
+    network::TransactionBuilder,
+    primitives::Address,
+    providers::Provider,
+    rpc::types::{BlockId, TransactionRequest},
+    transports::http::reqwest::Url,
+};
+use common::{constants::FIXTURE_RECEIPT_TIMEOUT, types::Backend};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+use tokio::{sync::OnceCell, time::timeout};
+
+/// A deployment of the fixture contracts, shared by every runner deploying
+/// them from the same key to the same node
+type Deployment = Arc<OnceCell<Vec<Address>>>;
+
+/// The deployments made so far, by the URL of the node and the deployer
+static DEPLOYMENTS: LazyLock<Mutex<HashMap<(String, Address), Deployment>>> =
+    LazyLock::new(Default::default);
+
+/// The runtime code of the fixture contracts, deployed before fuzzing so that
+/// transactions reach EVM execution instead of calling empty accounts
+pub const FIXTURES: [(&str, &str); 4] = [
+    // Proxy: forwards `calldata[2..]` to the precompile at `calldata[0..2]`
+    // with STATICCALL, and returns its output
+    ("proxy", "600236038060025f375f5f915f5f3560f01c5afa3d5f5f3e3d5ff3"),
+    // Recursion: with `n` as the first word of the calldata, writes a slot,
+    // a transient slot and calls itself with `n - 1`, then calls back into
+    // its caller
+    (
+        "recursion",
+        "5f3580156100285760019003805f5243815533815d5f5f60205f5f305af1505f5f5f5f5f335af1505b5000",
+    ),
+    // Storage: writes `2 * (n & 0xff)` slots, with `n` as the first word of
+    // the calldata
+    ("storage", "5f3560ff165b801561001d57600190035a8155438160801b55610005565b5000"),
+    // Delegation target: calls `calldata[0..20]` with `calldata[20..]` and
+    // the value of the call, and writes the caller to the first slot of the
+    // account delegating to it
+    ("delegation", "601436038060145f375f5f915f345f3560601c5af1335f555f5260205ff3"),
+];
+
+/// Deploys the fixture contracts from the key of the runner, unless
+/// `fixtures` is zero. They are deployed once per node and key, so the workers
+/// of an instance, and the instances started later, reuse the contracts of
+/// the first one, unless the node lost them since. The addresses are derived
+/// from the nonce of the runner, so they are known even if the node does not
+/// mine the deployments within `FIXTURE_RECEIPT_TIMEOUT` seconds.
+///
+/// # Arguments
+///
+/// * `provider` - The provider of the runner.
+/// * `rpc_url` - The URL of the node.
+/// * `sender` - The address of the runner.
+/// * `config` - The config of the runner.
+///
+/// # Returns
+///
+/// The addresses of the fixture contracts, in the order of `FIXTURES`.
+pub async fn deploy(
+    provider: &Backend,
+    rpc_url: &Url,
+    sender: Address,
+    config: &RunnerConfig,
+) -> Vec<Address> {
+    if config.fixtures == 0 {
+        return vec![];
+    }
+
+    let key = (rpc_url.to_string(), sender);
+    let mut deployment = DEPLOYMENTS.lock().unwrap().entry(key.clone()).or_default().clone();
+
+    // A node restarted since the deployment no longer has the contracts, so
+    // they are deployed again
+    if let Some(contracts) = deployment.get() {
+        if !deployed(provider, contracts).await {
+            let mut deployments = DEPLOYMENTS.lock().unwrap();
+            let entry = deployments.entry(key).or_default();
+            if Arc::ptr_eq(entry, &deployment) {
+                *entry = Deployment::default();
+            }
+            deployment = entry.clone();
+        }
+    }
+
+    deployment.get_or_init(|| send_deployments(provider, sender)).await.clone()
+}
+
+/// Returns whether the node has the code of the fixture contracts
+pub async fn deployed(provider: &Backend, contracts: &[Address]) -> bool {
+    let Some(contract) = contracts.last() else {
+        return false;
+    };
+    provider.get_code_at(*contract).await.is_ok_and(|code| !code.is_empty())
+}
+
+// Helper function to send the deployments of the fixture contracts and wait
+// for them to be mined
+async fn send_deployments(provider: &Backend, sender: Address) -> Vec<Address> {
+    let Ok(nonce) = provider.get_transaction_count(sender).block_id(BlockId::pending()).await
+    else {
+        return vec![];
+    };
+
+    let mut contracts = vec![];
+    let mut pending = vec![];
+    for (i, (_, runtime)) in FIXTURES.iter().enumerate() {
+        let runtime = hex::decode(runtime).unwrap();
+        let request = TransactionRequest::default()
+            .with_deploy_code(bytecode::deployment(&runtime))
+            .with_nonce(nonce + i as u64);
+
+        if let Ok(tx) = provider.send_transaction(request).await {
+            contracts.push(sender.create(nonce + i as u64));
+            pending.push(tx);
+        }
+    }
+
+    // Wait for the deployments, so that the nonce the runner starts with
+    // already accounts for them
+    let _ = timeout(Duration::from_secs(FIXTURE_RECEIPT_TIMEOUT), async {
+        for tx in pending {
+            let _ = tx.get_receipt().await;
+        }
+    })
+    .await;
+
+    contracts
+}
+
+#[test]
+fn test_fixtures_deployment() {
+    for (name, runtime) in FIXTURES {
+        let runtime = hex::decode(runtime).unwrap();
+        let initcode = bytecode::deployment(&runtime);
+
+        assert!(initcode.ends_with(&runtime), "{}", name);
+        assert_eq!(
+            u16::from_be_bytes([
+                initcode[1],
+                initcode[2]
+            ]) as usize,
+            runtime.len()
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    precompiles::{self, p256_signature},
+    signing::BlobForm,
+};
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+};
+use common::{constants::MAX_TRANSACTION_GAS_LIMIT, types::Backend};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// The forks generation can target, in order of activation
+pub const FORKS: [&str; 6] = [
+    "cancun", "prague", "osaka", "bpo1", "bpo2", "future",
+];
+
+/// Fork is the set of rules the node is expected to validate transactions
+/// with, either detected from the node or set in the config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Fork {
+    Cancun,
+    #[default]
+    Prague,
+    Osaka,
+    /// The first blob parameter only fork after Osaka
+    Bpo1,
+    /// The second blob parameter only fork after Osaka
+    Bpo2,
+    /// Whatever comes next, with one more transaction type
+    Future,
+}
+
+impl Fork {
+    /// Returns the fork before this one, or itself for the first one
+    pub fn previous(self) -> Self {
+        match self {
+            Fork::Cancun | Fork::Prague => Fork::Cancun,
+            Fork::Osaka => Fork::Prague,
+            Fork::Bpo1 => Fork::Osaka,
+            Fork::Bpo2 => Fork::Bpo1,
+            Fork::Future => Fork::Bpo2,
+        }
+    }
+
+    /// Returns the fork after this one, or itself for the last one
+    pub fn next(self) -> Self {
+        match self {
+            Fork::Cancun => Fork::Prague,
+            Fork::Prague => Fork::Osaka,
+            Fork::Osaka => Fork::Bpo1,
+            Fork::Bpo1 => Fork::Bpo2,
+            Fork::Bpo2 | Fork::Future => Fork::Future,
+        }
+    }
+
+    /// Returns the number of transaction types, which go from 0 up to it.
+    /// EIP-7702 transactions, and so authorizations, come with Prague.
+    pub fn transaction_types(self) -> u8 {
+        match self {
+            Fork::Cancun => 4,
+            Fork::Future => 6,
+            _ => 5,
+        }
+    }
+
+    /// Returns the maximum number of blobs per block, from EIP-7691 and the
+    /// blob parameter only forks of EIP-7892
+    pub fn max_blobs_per_block(self) -> usize {
+        match self {
+            Fork::Cancun => 6,
+            Fork::Prague | Fork::Osaka => 9,
+            Fork::Bpo1 => 15,
+            Fork::Bpo2 | Fork::Future => 21,
+        }
+    }
+
+    /// Returns the maximum number of blobs per transaction, which EIP-7594
+    /// caps below the one per block
+    pub fn max_blobs_per_tx(self) -> usize {
+        match self {
+            Fork::Cancun | Fork::Prague => self.max_blobs_per_block(),
+            _ => 6,
+        }
+    }
+
+    /// Returns the update fraction of the blob base fee
+    pub fn blob_update_fraction(self) -> u128 {
+        match self {
+            Fork::Cancun => 3338477,
+            Fork::Prague | Fork::Osaka => 5007716,
+            Fork::Bpo1 => 8346193,
+            Fork::Bpo2 | Fork::Future => 11684671,
+        }
+    }
+
+    /// Returns whether calldata is charged at least the floor of EIP-7623,
+    /// which comes with Prague
+    pub fn calldata_floor(self) -> bool {
+        self >= Fork::Prague
+    }
+
+    /// Returns the gas limit cap of EIP-7825, if any
+    pub fn max_transaction_gas(self) -> Option<u64> {
+        (self >= Fork::Osaka).then_some(MAX_TRANSACTION_GAS_LIMIT)
+    }
+
+    /// Returns the envelope form blob transactions are sent in. The network
+    /// form is the one of the fork, which has cell proofs since Osaka.
+    pub fn blob_form(self, form: BlobForm) -> BlobForm {
+        match form {
+            BlobForm::Network if self >= Fork::Osaka => BlobForm::Cells,
+            form => form,
+        }
+    }
+
+    /// Returns the fork set in the config, or the one of the node if unset
+    pub async fn resolve(fork: Option<Fork>, provider: &Backend) -> Self {
+        match fork {
+            Some(fork) => fork,
+            None => Self::detect(provider).await,
+        }
+    }
+
+    /// Detects the fork of the node with `eth_config` from EIP-7910 or, if
+    /// it is not supported, by calling the precompiles each fork adds
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The fork, which is `Cancun` if none of the probes succeed.
+    pub async fn detect(provider: &Backend) -> Self {
+        if let Ok(config) = provider.client().request_noparams::<Value>("eth_config").await {
+            if let Some(fork) = Self::from_config(&config) {
+                return fork;
+            }
+        }
+
+        // P256VERIFY returns one for a valid signature since Osaka, and an
+        // empty output before, as any call to an account without code
+        let mut random = StdRng::seed_from_u64(0);
+        let signature = p256_signature(&mut random);
+        let input = signature.iter().flat_map(|word| word.to_be_bytes_vec()).collect::<Vec<_>>();
+        if !Self::probe(provider, 0x100, input).await.is_empty() {
+            return Fork::Osaka;
+        }
+
+        // G1ADD of two points at infinity returns a point at infinity since
+        // Prague
+        if !Self::probe(provider, 0x0b, vec![0u8; 256]).await.is_empty() {
+            return Fork::Prague;
+        }
+
+        Fork::Cancun
+    }
+
+    // Helper function to read the fork out of the `current` field of the
+    // response of `eth_config`
+    fn from_config(config: &Value) -> Option<Self> {
+        let current = config.get("current")?;
+        let has_precompile = |precompile: u64| {
+            current.get("precompiles").and_then(Value::as_object).is_some_and(|precompiles| {
+                precompiles.values().any(|address| {
+                    address.as_str().and_then(|address| address.parse::<Address>().ok())
+                        == Some(precompiles::address(precompile))
+                })
+            })
+        };
+        let max_blobs = current.get("blobSchedule")?.get("max")?.as_u64()?;
+
+        Some(if has_precompile(0x100) {
+            match max_blobs {
+                0..=9 => Fork::Osaka,
+                10..=15 => Fork::Bpo1,
+                16..=21 => Fork::Bpo2,
+                _ => Fork::Future,
+            }
+        } else if has_precompile(0x0b) {
+            Fork::Prague
+        } else {
+            Fork::Cancun
+        })
+    }
+
+    // Helper function to call a precompile, returning its output or nothing
+    // if the call fails
+    async fn probe(provider: &Backend, precompile: u64, input: Vec<u8>) -> Bytes {
+        let request = TransactionRequest::default()
+            .to(precompiles::address(precompile))
+            .input(Bytes::from(input).into())
+            .value(U256::ZERO);
+        provider.call(request).await.unwrap_or_default()
+    }
+}
+
+impl Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", FORKS[*self as usize])
+    }
+}
+
+impl FromStr for Fork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cancun" => Ok(Fork::Cancun),
+            "prague" => Ok(Fork::Prague),
+            "osaka" => Ok(Fork::Osaka),
+            "bpo1" => Ok(Fork::Bpo1),
+            "bpo2" => Ok(Fork::Bpo2),
+            "future" => Ok(Fork::Future),
+            _ => Err(format!("invalid fork: {}", s)),
+        }
+    }
+}
+
+#[test]
+fn test_fork_config() {
+    use serde_json::json;
+
+    let config = json!({
+        "current": {
+            "blobSchedule": { "max": 15, "target": 10, "baseFeeUpdateFraction": 8346193 },
+            "precompiles": {
+                "BLS12_G1ADD": "0x000000000000000000000000000000000000000b",
+                "P256VERIFY": "0x0000000000000000000000000000000000000100"
+            }
+        }
+    });
+    assert_eq!(Fork::from_config(&config), Some(Fork::Bpo1));
+    assert_eq!(Fork::Bpo1.previous().max_blobs_per_tx(), 6);
+    assert_eq!(Fork::Cancun.next().transaction_types(), 5);
+    assert!(!Fork::Cancun.calldata_floor() && Fork::Prague.calldata_floor());
+    assert_eq!(Fork::Prague.max_transaction_gas(), None);
+
+    for fork in FORKS {
+        assert_eq!(fork.parse::<Fork>().unwrap().to_string(), fork);
+    }
+}
@@ -0,0 +1,70 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rngs::StdRng};
+use sha2::Sha256;
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// JwtSecret is the secret shared with the node to authenticate to the Engine
+/// API. Tokens are HS256 JWTs with an `iat` claim, which the node accepts
+/// within a minute of its own clock.
+#[derive(Clone, Copy)]
+pub struct JwtSecret([u8; 32]);
+
+impl JwtSecret {
+    /// Reads the hex-encoded secret from a file, as written by the clients
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let content = content.trim();
+        let bytes = hex::decode(content.strip_prefix("0x").unwrap_or(content))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let secret: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| format!("{}: the secret must be 32 bytes long", path.display()))?;
+
+        Ok(Self(secret))
+    }
+
+    /// Creates a random secret, which the node should reject
+    pub fn random(random: &mut StdRng) -> Self {
+        let mut secret = [0u8; 32];
+        random.fill_bytes(&mut secret);
+        Self(secret)
+    }
+
+    /// Creates a token issued `age` seconds ago
+    pub fn token(&self, age: u64) -> String {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .saturating_sub(age);
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(format!(r#"{{"iat":{}}}"#, iat));
+        let message = format!("{}.{}", header, claims);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0).unwrap();
+        mac.update(message.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        format!("{}.{}", message, signature)
+    }
+}
+
+#[test]
+fn test_jwt_token() {
+    let path = std::env::temp_dir().join("rakoon_jwt.hex");
+    fs::write(&path, format!("0x{}", "ab".repeat(32))).unwrap();
+
+    let secret = JwtSecret::from_file(&path).unwrap();
+    let token = secret.token(0);
+    assert_eq!(token.split('.').count(), 3);
+    assert!(token.starts_with("eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9."));
+
+    let _ = fs::remove_file(path);
+}
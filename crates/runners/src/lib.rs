@@ -4,14 +4,34 @@ use std::{
 };
 
 pub mod al;
+pub mod batch;
 pub mod blob;
 pub mod builder;
+pub mod bytecode;
 pub mod cache;
+pub mod cells;
+pub mod checkpoint;
+pub mod config;
+pub mod control;
 pub mod eip1559;
 pub mod eip7702;
+pub mod engine;
+pub mod envelope;
+pub mod fixtures;
+pub mod fork;
+pub mod jwt;
 pub mod legacy;
 pub mod logger;
+pub mod methods;
+pub mod network;
+pub mod nonces;
+pub mod pipeline;
+pub mod precompiles;
 pub mod random;
+pub mod scenario;
+pub mod senders;
+pub mod signing;
+pub mod transaction;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Runner {
@@ -19,8 +39,11 @@ pub enum Runner {
     Blob,
     EIP1559,
     EIP7702,
+    Engine,
+    Envelope,
     Legacy,
     Random,
+    Scenario,
 }
 
 impl Display for Runner {
@@ -30,8 +53,11 @@ impl Display for Runner {
             Runner::Blob => write!(f, "blob"),
             Runner::EIP1559 => write!(f, "eip1559"),
             Runner::EIP7702 => write!(f, "eip7702"),
+            Runner::Engine => write!(f, "engine"),
+            Runner::Envelope => write!(f, "envelope"),
             Runner::Legacy => write!(f, "legacy"),
             Runner::Random => write!(f, "random"),
+            Runner::Scenario => write!(f, "scenario"),
         }
     }
 }
@@ -45,8 +71,11 @@ impl FromStr for Runner {
             "blob" => Ok(Runner::Blob),
             "eip1559" => Ok(Runner::EIP1559),
             "eip7702" => Ok(Runner::EIP7702),
+            "engine" => Ok(Runner::Engine),
+            "envelope" => Ok(Runner::Envelope),
             "legacy" => Ok(Runner::Legacy),
             "random" => Ok(Runner::Random),
+            "scenario" => Ok(Runner::Scenario),
             _ => Err(format!("invalid runner: {}", s)),
         }
     }
@@ -1,15 +1,22 @@
+use crate::pipeline::Response;
 use alloy::transports::{RpcError, TransportErrorKind};
 use chrono::Local;
 use std::{
+    collections::BTreeMap,
     fs::{File, OpenOptions},
     io::{self, Write},
+    mem,
     path::Path,
+    time::Duration,
 };
 
 /// Logger structure for writing logs to a file with timestamp
 pub struct Logger {
     file: File,
     runner_name: String,
+    /// The payloads answered since the last good response, by the index they
+    /// were sent with
+    answered: BTreeMap<u64, Vec<u8>>,
 }
 
 impl Logger {
@@ -21,6 +28,10 @@ impl Logger {
         let reports_dir = format!("reports/{}", runner_name);
         std::fs::create_dir_all(&reports_dir)?;
 
+        // Create corpus directory for the runner if it doesn't exist
+        let corpus_dir = format!("corpus/{}", runner_name);
+        std::fs::create_dir_all(&corpus_dir)?;
+
         Ok(())
     }
 
@@ -47,7 +58,7 @@ impl Logger {
         file.write_all(startup_message.as_bytes())?;
         file.flush()?;
 
-        Ok(Self { file, runner_name: runner_name.to_string() })
+        Ok(Self { file, runner_name: runner_name.to_string(), answered: BTreeMap::new() })
     }
 
     /// Logs a message with timestamp
@@ -84,7 +95,17 @@ impl Logger {
         self.file.flush()
     }
 
-    pub fn generate_crash_report(&mut self, crash_data: &[u8]) -> io::Result<()> {
+    /// Writes a crash report with the payloads that may have crashed the node
+    ///
+    /// # Arguments
+    ///
+    /// * `crash_data` - The payloads sent since the last good response, in
+    ///   the order they were sent
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or an IO error
+    pub fn generate_crash_report(&mut self, crash_data: &[Vec<u8>]) -> io::Result<()> {
         let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
         let report_filename =
             format!("reports/{}/crash_report_{}.txt", self.runner_name, timestamp);
@@ -92,16 +113,181 @@ impl Logger {
         let mut report_file =
             OpenOptions::new().create(true).write(true).open(Path::new(&report_filename))?;
 
-        let formatted_data =
-            format!("Transaction that crashed the node (hex): 0x{}\n", hex::encode(crash_data));
+        // With several requests in flight, any of those sent since the last
+        // good response may be the one that crashed the node
+        let mut formatted_data = format!(
+            "Requests sent since the last good response, in send order ({}):\n",
+            crash_data.len()
+        );
+        for payload in crash_data {
+            formatted_data.push_str(&format!("0x{}\n", hex::encode(payload)));
+        }
         report_file.write_all(formatted_data.as_bytes())?;
 
         report_file.flush()?;
         Ok(())
     }
 
+    /// Saves the transaction the runner was mutating when it stopped, so it
+    /// can be used as a starting point for later campaigns
+    ///
+    /// # Arguments
+    ///
+    /// * `current_tx` - The transaction being mutated by the runner
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or an IO error
+    pub fn save_corpus(&mut self, current_tx: &[u8]) -> io::Result<()> {
+        if current_tx.is_empty() {
+            return Ok(());
+        }
+
+        // Down to the millisecond, so that a runner restarted within the same
+        // second does not overwrite the file of its previous session
+        let timestamp = Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let corpus_filename = format!("corpus/{}/corpus_{}.txt", self.runner_name, timestamp);
+
+        let mut corpus_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(Path::new(&corpus_filename))?;
+
+        corpus_file.write_all(format!("0x{}\n", hex::encode(current_tx)).as_bytes())?;
+        corpus_file.flush()
+    }
+
+    /// Writes a summary of the session once the runner has stopped
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - How long the runner has been running
+    /// * `tx_counter` - The number of transactions sent by the runner
+    /// * `crash_counter` - The number of crashes found by the runner
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or an IO error
+    pub fn generate_session_summary(
+        &mut self,
+        elapsed: Duration,
+        tx_counter: u64,
+        crash_counter: u64,
+    ) -> io::Result<()> {
+        let timestamp = Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let summary_filename =
+            format!("reports/{}/session_summary_{}.txt", self.runner_name, timestamp);
+
+        let mut summary_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(Path::new(&summary_filename))?;
+
+        let summary = format!(
+            "Runner: {}\nElapsed: {}s\nTransactions sent: {}\nCrashes found: {}\n",
+            self.runner_name,
+            elapsed.as_secs(),
+            tx_counter,
+            crash_counter
+        );
+        summary_file.write_all(summary.as_bytes())?;
+        summary_file.flush()?;
+
+        self.log(&format!(
+            "Logger for {} stopped after {} transactions and {} crashes",
+            self.runner_name, tx_counter, crash_counter
+        ))
+    }
+
+    /// Flushes the log file
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// Checks the responses received by the runner and, if the node crashed,
+    /// generates a crash report with every request sent since the last good
+    /// response, as any of them may have crashed it
+    ///
+    /// # Arguments
+    ///
+    /// * `responses` - The responses received, matched with their payload
+    /// * `in_flight` - The requests still waiting for a response
+    ///
+    /// # Returns
+    ///
+    /// Whether the node crashed or not
+    pub fn check_responses<'a>(
+        &mut self,
+        responses: Vec<Response>,
+        in_flight: impl Iterator<Item = (u64, &'a Vec<u8>)>,
+    ) -> bool {
+        let mut crashed = false;
+        for Response { index, payload, result } in responses {
+            match result {
+                // The node answered, so everything sent before was handled
+                Ok(()) => self.answered.retain(|answered, _| *answered > index),
+                Err(err) if !matches!(err, RpcError::Transport(_)) => {
+                    self.answered.retain(|answered, _| *answered > index)
+                }
+                Err(err) => {
+                    crashed |= self.is_connection_refused_error(&err);
+                    self.answered.insert(index, payload);
+                }
+            }
+        }
+
+        if crashed {
+            let mut payloads = mem::take(&mut self.answered);
+            payloads.extend(in_flight.map(|(index, payload)| (index, payload.clone())));
+            let payloads: Vec<Vec<u8>> = payloads.into_values().collect();
+            let _ = self.generate_crash_report(&payloads);
+        }
+
+        crashed
+    }
+
     pub fn is_connection_refused_error(&self, err: &RpcError<TransportErrorKind>) -> bool {
+        // WebSocket and IPC transports do not report the refused connection,
+        // but that their backend is gone once they fail to reconnect
         let formatted_err = format!("{:#?}", err);
         formatted_err.contains("Connection refused")
+            || matches!(err, RpcError::Transport(TransportErrorKind::BackendGone))
     }
 }
+
+#[test]
+fn test_crash_report_order() {
+    let _ = std::fs::remove_dir_all("reports/logger-test");
+    let mut logger = Logger::new("logger-test").unwrap();
+    let response = |index: u64, result| Response { index, payload: vec![index as u8], result };
+
+    // A request that timed out is only suspect until the node answers again
+    let responses = vec![
+        response(1, Err(TransportErrorKind::custom_str("timed out"))),
+        response(0, Ok(())),
+        response(2, Ok(())),
+    ];
+    assert!(!logger.check_responses(responses, std::iter::empty()));
+
+    let responses = vec![
+        response(4, Err(TransportErrorKind::custom_str("timed out"))),
+        response(3, Err(RpcError::Transport(TransportErrorKind::BackendGone))),
+    ];
+    let in_flight = [
+        (5, vec![5]),
+        (6, vec![6]),
+    ];
+    assert!(logger.check_responses(responses, in_flight.iter().map(|(i, p)| (*i, p))));
+
+    let report = std::fs::read_dir("reports/logger-test").unwrap().next().unwrap().unwrap();
+    let report = std::fs::read_to_string(report.path()).unwrap();
+    let payloads: Vec<&str> = report.lines().skip(1).collect();
+    assert_eq!(
+        payloads,
+        [
+            "0x03", "0x04", "0x05", "0x06"
+        ]
+    );
+}
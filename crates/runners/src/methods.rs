@@ -0,0 +1,190 @@
+use alloy::{
+    hex,
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    transports::TransportResult,
+};
+use common::types::Backend;
+use rand::{Rng, RngCore, rngs::StdRng};
+use serde_json::{Map, Value, json};
+
+/// The default way of sending transactions, that is, `eth_sendTransaction`
+/// for generated requests and `eth_sendRawTransaction` for mutated ones
+pub const SEND: &str = "send";
+
+/// The methods generated requests can be routed to. All of them but `send`
+/// execute the transaction in the EVM without any fee gatekeeping.
+pub const METHODS: [&str; 7] = [
+    SEND,
+    "eth_call",
+    "eth_estimateGas",
+    "eth_createAccessList",
+    "eth_simulateV1",
+    "debug_traceCall",
+    "eth_signTransaction",
+];
+
+/// Builds the params of a call to `method` carrying the given request, with a
+/// random block tag and random state overrides where the method takes them
+///
+/// # Arguments
+///
+/// * `method` - One of `METHODS`, other than `send`.
+/// * `request` - The request to route to the method.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The params of the call, as a JSON array.
+pub fn params(method: &str, request: &TransactionRequest, random: &mut StdRng) -> Value {
+    // The accounts touched by the request are the most interesting ones to
+    // override the state of
+    let targets = [
+        request.from,
+        request.to.and_then(|to| to.to().copied()),
+    ];
+    let request = json!(request);
+
+    match method {
+        "eth_call" | "eth_estimateGas" => {
+            // 50% chance to override the state the call is executed against
+            if random.random_bool(0.5) {
+                json!([
+                    request,
+                    block_tag(random),
+                    state_override(&targets, random)
+                ])
+            } else {
+                json!([
+                    request,
+                    block_tag(random)
+                ])
+            }
+        }
+        "eth_createAccessList" => json!([
+            request,
+            block_tag(random)
+        ]),
+        "eth_simulateV1" => {
+            let mut calls = vec![request.clone()];
+            // 25% chance to simulate the same call several times in a row
+            if random.random_bool(0.25) {
+                calls.extend(vec![request; random.random_range(1..16)]);
+            }
+
+            json!([
+                {
+                    "blockStateCalls": [
+                        {
+                            "stateOverrides": state_override(&targets, random),
+                            "calls": calls,
+                        }
+                    ],
+                    "validation": random.random_bool(0.5),
+                    "traceTransfers": random.random_bool(0.5),
+                },
+                block_tag(random),
+            ])
+        }
+        "debug_traceCall" => {
+            let tracer = match random.random_range(0..4) {
+                0 => json!("callTracer"),
+                1 => json!("prestateTracer"),
+                2 => json!("4byteTracer"),
+                _ => Value::Null,
+            };
+
+            json!([
+                request,
+                block_tag(random),
+                { "tracer": tracer, "stateOverrides": state_override(&targets, random) },
+            ])
+        }
+        _ => json!([request]),
+    }
+}
+
+/// Calls `method` with the given params. The result is discarded, as only
+/// failures of the request itself matter to the crash oracle.
+pub async fn call(provider: Backend, method: &'static str, params: Value) -> TransportResult<()> {
+    provider.client().request::<_, Value>(method, params).await.map(|_| ())
+}
+
+// Helper function to create a random block tag, valid or not
+fn block_tag(random: &mut StdRng) -> Value {
+    match random.random_range(0..10) {
+        0 => json!("pending"),
+        1 => json!("earliest"),
+        2 => json!("safe"),
+        3 => json!("finalized"),
+        4 => json!(format!("0x{:x}", random.random::<u64>())),
+        5 => json!({ "blockHash": B256::from(random.random::<[u8; 32]>()) }),
+        6 => json!({ "blockNumber": format!("0x{:x}", random.random_range(0..1024)) }),
+        7 => json!("0x"),
+        _ => json!("latest"),
+    }
+}
+
+// Helper function to create random state overrides for up to a few accounts,
+// with both `state` and `stateDiff` from time to time, which is invalid
+fn state_override(targets: &[Option<Address>], random: &mut StdRng) -> Value {
+    let mut overrides = Map::new();
+
+    for _ in 0..random.random_range(1..4) {
+        let mut account = Map::new();
+        if random.random_bool(0.5) {
+            account.insert(
+                "balance".to_string(),
+                json!(U256::from_be_bytes(random.random::<[u8; 32]>())),
+            );
+        }
+        if random.random_bool(0.5) {
+            account.insert("nonce".to_string(), json!(format!("0x{:x}", random.random::<u64>())));
+        }
+        if random.random_bool(0.5) {
+            let mut code = vec![0u8; random.random_range(0..1024)];
+            random.fill_bytes(&mut code);
+            account.insert("code".to_string(), json!(format!("0x{}", hex::encode(code))));
+        }
+        for field in [
+            "state",
+            "stateDiff",
+        ] {
+            if random.random_bool(0.3) {
+                let mut slots = Map::new();
+                for _ in 0..random.random_range(1..8) {
+                    slots.insert(
+                        B256::from(random.random::<[u8; 32]>()).to_string(),
+                        json!(B256::from(random.random::<[u8; 32]>())),
+                    );
+                }
+                account.insert(field.to_string(), Value::Object(slots));
+            }
+        }
+        if random.random_bool(0.1) {
+            account.insert(
+                "movePrecompileToAddress".to_string(),
+                json!(Address::from(random.random::<[u8; 20]>())),
+            );
+        }
+
+        let address = targets[random.random_range(0..targets.len())]
+            .filter(|_| random.random_bool(0.5))
+            .unwrap_or_else(|| Address::from(random.random::<[u8; 20]>()));
+        overrides.insert(address.to_string(), Value::Object(account));
+    }
+
+    Value::Object(overrides)
+}
+
+#[test]
+fn test_method_params() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let request = TransactionRequest::default();
+    for method in &METHODS[1..] {
+        assert!(params(method, &request, &mut random).is_array());
+    }
+}
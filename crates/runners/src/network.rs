@@ -0,0 +1,199 @@
+use crate::{config::RunnerConfig, fork::Fork};
+use alloy::{primitives::Address, transports::http::reqwest::Url};
+use common::{
+    constants::{ANVIL_SK, KURTOSIS_SK, SK, STATIC_KEYS},
+    parse_sk,
+};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The names of the network presets
+pub const NETWORKS: [&str; 4] = [
+    "anvil", "geth-dev", "reth-dev", "kurtosis",
+];
+
+/// Network is a profile of a target node: its chain id, the key that funds
+/// the runners, its fork, its endpoints and the limits it copes with. It is
+/// either one of `NETWORKS` or read from a genesis file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    pub name: String,
+    /// The chain id the node is expected to answer `eth_chainId` with
+    pub chain_id: u64,
+    pub rpc_url: Url,
+    pub engine_url: Url,
+    pub jwt_secret: PathBuf,
+    /// A key funded in the genesis of the node
+    pub sk: String,
+    /// The fork of the node, or `None` to detect it
+    pub fork: Option<Fork>,
+    pub in_flight: usize,
+    pub max_tps: u64,
+    pub senders: usize,
+}
+
+impl Network {
+    /// Returns the preset with the given name, if any
+    pub fn preset(name: &str) -> Option<Self> {
+        let mut network = Self {
+            name: name.to_string(),
+            chain_id: 1337,
+            rpc_url: Url::parse("http://localhost:8545").unwrap(),
+            engine_url: Url::parse("http://localhost:8551").unwrap(),
+            jwt_secret: PathBuf::from("jwt.hex"),
+            sk: ANVIL_SK.to_string(),
+            fork: None,
+            in_flight: 1,
+            max_tps: 0,
+            senders: 0,
+        };
+
+        match name {
+            // anvil mines every transaction on arrival, so requests are sent
+            // one by one
+            "anvil" => network.chain_id = 31337,
+            // geth --dev funds a fresh developer account, so the key of `SK`
+            // has to be imported as it with --dev.keystore or similar
+            "geth-dev" => {
+                network.sk = SK.to_string();
+                network.in_flight = 4;
+            }
+            // reth --dev funds the accounts of the same mnemonic as anvil
+            "reth-dev" => network.in_flight = 8,
+            // The ethereum-package of Kurtosis funds its own list of keys,
+            // and its nodes are usually behind mapped ports
+            "kurtosis" => {
+                network.chain_id = 3151908;
+                network.sk = KURTOSIS_SK.to_string();
+                network.in_flight = 16;
+                network.max_tps = 500;
+                network.senders = 16;
+            }
+            _ => return None,
+        }
+
+        Some(network)
+    }
+
+    /// Reads a profile out of a genesis file in the format of geth
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the genesis file.
+    ///
+    /// # Returns
+    ///
+    /// The profile, named after the file, with the fork active at this time
+    /// and the first known key funded in the genesis.
+    pub fn from_genesis(path: &Path) -> Result<Self, String> {
+        let genesis = fs::read_to_string(path)
+            .ok()
+            .and_then(|genesis| serde_json::from_str::<Value>(&genesis).ok())
+            .ok_or_else(|| format!("invalid genesis file: {}", path.display()))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+        let name = path.file_stem().map_or("genesis".into(), |stem| stem.to_string_lossy());
+        Self::from_genesis_json(&name, &genesis, now)
+    }
+
+    // Helper function to build a profile out of a parsed genesis file, with
+    // the fork active at `now`
+    fn from_genesis_json(name: &str, genesis: &Value, now: u64) -> Result<Self, String> {
+        let config = genesis.get("config").ok_or("invalid genesis: missing config")?;
+        let chain_id = config
+            .get("chainId")
+            .and_then(Value::as_u64)
+            .ok_or("invalid genesis: missing chainId")?;
+
+        // The last fork scheduled at or before now, as the node reports it
+        let schedule = [
+            ("cancunTime", Fork::Cancun),
+            ("pragueTime", Fork::Prague),
+            ("osakaTime", Fork::Osaka),
+            ("bpo1Time", Fork::Bpo1),
+            ("bpo2Time", Fork::Bpo2),
+        ];
+        let fork = schedule
+            .iter()
+            .filter(|(key, _)| config.get(*key).and_then(Value::as_u64).is_some_and(|t| t <= now))
+            .map(|(_, fork)| *fork)
+            .next_back();
+
+        // Addresses in the alloc may come with or without prefix and checksum
+        let funded = |sk: &str| {
+            let address = Address::from_private_key(&parse_sk(sk).unwrap());
+            genesis.get("alloc").and_then(Value::as_object).is_some_and(|alloc| {
+                alloc.keys().any(|key| {
+                    key.trim_start_matches("0x").eq_ignore_ascii_case(&hex::encode(address))
+                })
+            })
+        };
+        let sk = [
+            ANVIL_SK,
+            SK,
+            KURTOSIS_SK,
+        ]
+        .into_iter()
+        .chain(STATIC_KEYS)
+        .find(|sk| funded(sk))
+        .ok_or("invalid genesis: no known key is funded")?;
+
+        let mut network = Self::preset("anvil").unwrap();
+        network.name = name.to_string();
+        network.chain_id = chain_id;
+        network.sk = sk.to_string();
+        network.fork = fork;
+        Ok(network)
+    }
+
+    /// Applies the endpoints, fork and limits of the profile to a config
+    pub fn apply(&self, config: &mut RunnerConfig) {
+        config.engine_url = self.engine_url.clone();
+        config.jwt_secret = self.jwt_secret.clone();
+        config.fork = self.fork;
+        config.in_flight = self.in_flight;
+        config.max_tps = self.max_tps;
+        config.senders = self.senders;
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    /// Parses either the name of a preset or the path of a genesis file
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(network) = Self::preset(s) {
+            return Ok(network);
+        }
+
+        let path = Path::new(s);
+        if !path.is_file() {
+            return Err(format!("invalid network: {}", s));
+        }
+        Self::from_genesis(path)
+    }
+}
+
+#[test]
+fn test_network_genesis() {
+    use serde_json::json;
+
+    let genesis = json!({
+        "config": { "chainId": 7, "cancunTime": 0, "pragueTime": 100, "osakaTime": 200 },
+        "alloc": { "f39Fd6e51aad88F6F4ce6aB8827279cffFb92266": { "balance": "0x1" } }
+    });
+    let network = Network::from_genesis_json("devnet", &genesis, 150).unwrap();
+    assert_eq!(network.chain_id, 7);
+    assert_eq!(network.fork, Some(Fork::Prague));
+    assert_eq!(network.sk, ANVIL_SK);
+
+    assert!(Network::from_genesis_json("devnet", &json!({ "config": {} }), 0).is_err());
+    for name in NETWORKS {
+        assert_eq!(name.parse::<Network>().unwrap().name, name);
+    }
+}
@@ -0,0 +1,301 @@
+use alloy::{primitives::Address, rpc::types::TransactionRequest};
+use common::constants::{MAX_NONCE_GAP, MAX_PENDING_NONCES};
+use rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// The nonce patterns, in the order of their weights in a `NonceMix`
+pub const PATTERNS: [&str; 5] = [
+    "sequential",
+    "gap",
+    "replacement",
+    "underpriced",
+    "max",
+];
+
+/// NoncePattern is the way the nonce of a generated transaction relates to
+/// the ones sent before by the same sender
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoncePattern {
+    /// The next pending nonce
+    Sequential,
+    /// A nonce ahead of the next pending one, which lands in the queue
+    Gap,
+    /// The last sent nonce again, with higher fees
+    Replacement,
+    /// The last sent nonce again, with fees too low to replace it
+    Underpriced,
+    /// The highest nonce, `2^64 - 1`, which no account can ever use
+    Max,
+}
+
+impl NoncePattern {
+    /// Scales the fees of the transaction sent before with the same nonce, so
+    /// that the new one replaces it, or fails to
+    ///
+    /// # Arguments
+    ///
+    /// * `last` - The fees of the transaction sent before with the nonce.
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The fees of the new transaction, or `None` if the pattern does not
+    /// reuse a nonce.
+    pub fn adjust_fees(&self, last: Fees, random: &mut StdRng) -> Option<Fees> {
+        // Pools ask for a 10% bump, and blob pools for a 100% one, so blob
+        // replacements always double the fees. Bumps short of it are left to
+        // underpriced transactions
+        match self {
+            NoncePattern::Replacement if last.max_fee_per_blob_gas > 0 => {
+                Some(last.bump(random.random_range(200..=300)))
+            }
+            NoncePattern::Replacement => Some(last.bump(random.random_range(110..=300))),
+            NoncePattern::Underpriced => Some(last.scale(random.random_range(50..110))),
+            _ => None,
+        }
+    }
+}
+
+/// Fees are the fees a transaction was sent with, which the ones reusing its
+/// nonce are priced against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fees {
+    pub gas_price: u128,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_blob_gas: u128,
+}
+
+impl Fees {
+    /// Scales every fee by a percent, rounding down
+    pub fn scale(&self, percent: u128) -> Self {
+        let scale = |fee: u128| fee.saturating_mul(percent) / 100;
+        Self {
+            gas_price: scale(self.gas_price),
+            max_fee_per_gas: scale(self.max_fee_per_gas),
+            max_priority_fee_per_gas: scale(self.max_priority_fee_per_gas),
+            max_fee_per_blob_gas: scale(self.max_fee_per_blob_gas),
+        }
+    }
+
+    /// Bumps every fee by a percent, rounding up, so that the bump is never
+    /// short of it, not even for fees of a few wei
+    pub fn bump(&self, percent: u128) -> Self {
+        let bump = |fee: u128| fee.max(1).saturating_mul(percent).div_ceil(100);
+        Self {
+            gas_price: bump(self.gas_price),
+            max_fee_per_gas: bump(self.max_fee_per_gas),
+            max_priority_fee_per_gas: bump(self.max_priority_fee_per_gas),
+            max_fee_per_blob_gas: bump(self.max_fee_per_blob_gas),
+        }
+    }
+}
+
+impl From<&TransactionRequest> for Fees {
+    fn from(request: &TransactionRequest) -> Self {
+        // Pools price legacy transactions as if both fees were the gas price,
+        // so a transaction of any type can replace one of any other
+        let gas_price = request.gas_price.or(request.max_fee_per_gas).unwrap_or_default();
+        Self {
+            gas_price,
+            max_fee_per_gas: request.max_fee_per_gas.unwrap_or(gas_price),
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas.unwrap_or(gas_price),
+            max_fee_per_blob_gas: request.max_fee_per_blob_gas.unwrap_or_default(),
+        }
+    }
+}
+
+/// NonceMix holds the weights of each pattern, as typed in the terminal, for
+/// instance `sequential:80,gap:10,replacement:10`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonceMix([u32; 5]);
+
+impl Default for NonceMix {
+    fn default() -> Self {
+        Self([
+            80, 5, 5, 5, 5,
+        ])
+    }
+}
+
+impl NonceMix {
+    /// Picks a pattern according to the weights
+    pub fn pick(&self, random: &mut StdRng) -> NoncePattern {
+        let total: u32 = self.0.iter().sum();
+        let mut target = random.random_range(0..total);
+
+        for (i, weight) in self.0.iter().enumerate() {
+            if target < *weight {
+                return match i {
+                    0 => NoncePattern::Sequential,
+                    1 => NoncePattern::Gap,
+                    2 => NoncePattern::Replacement,
+                    3 => NoncePattern::Underpriced,
+                    _ => NoncePattern::Max,
+                };
+            }
+            target -= weight;
+        }
+
+        NoncePattern::Sequential
+    }
+}
+
+impl Display for NonceMix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let weights = PATTERNS
+            .iter()
+            .zip(self.0.iter())
+            .filter(|(_, weight)| **weight > 0)
+            .map(|(pattern, weight)| format!("{}:{}", pattern, weight))
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", weights.join(","))
+    }
+}
+
+impl FromStr for NonceMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = [0; 5];
+
+        for entry in s.split(',') {
+            let (pattern, weight) = entry.split_once(':').unwrap_or((entry, "1"));
+            let index = PATTERNS
+                .iter()
+                .position(|p| *p == pattern)
+                .ok_or_else(|| format!("invalid nonce pattern: {}", pattern))?;
+            weights[index] =
+                weight.parse().map_err(|_| format!("invalid nonce weight: {}", weight))?;
+        }
+
+        if weights.iter().sum::<u32>() == 0 {
+            return Err(format!("invalid nonces: {}", s));
+        }
+
+        Ok(Self(weights))
+    }
+}
+
+/// NonceManager tracks the next pending nonce of each sender locally, as the
+/// node only learns about them once the transactions are accepted, and the
+/// fees of the last transaction sent with each nonce, which replacements are
+/// priced against
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct NonceManager {
+    pending: HashMap<Address, u64>,
+    fees: HashMap<Address, BTreeMap<u64, Fees>>,
+}
+
+impl NonceManager {
+    /// Starts tracking a sender from the given nonce, if not tracked yet
+    pub fn track(&mut self, address: Address, nonce: u64) {
+        self.pending.entry(address).or_insert(nonce);
+    }
+
+    /// Syncs the nonce of a sender with the one of the node. The local one is
+    /// kept while ahead, unless it is so far ahead that the transactions in
+    /// between must have been dropped.
+    pub fn sync(&mut self, address: Address, nonce: u64) {
+        let pending = self.pending.entry(address).or_insert(nonce);
+        if *pending < nonce || *pending > nonce.saturating_add(MAX_PENDING_NONCES) {
+            *pending = nonce;
+        }
+
+        // Mined nonces cannot be replaced anymore
+        if let Some(fees) = self.fees.get_mut(&address) {
+            *fees = fees.split_off(&nonce);
+        }
+    }
+
+    /// Records the fees a transaction of a sender was sent with, unless it
+    /// was not meant to replace the one sent before with its nonce
+    pub fn record(&mut self, address: Address, nonce: u64, pattern: NoncePattern, fees: Fees) {
+        if matches!(pattern, NoncePattern::Underpriced | NoncePattern::Max) {
+            return;
+        }
+        self.fees.entry(address).or_default().insert(nonce, fees);
+    }
+
+    /// Returns the fees of the last transaction a sender sent with a nonce,
+    /// if any
+    pub fn fees(&self, address: Address, nonce: u64) -> Option<Fees> {
+        self.fees.get(&address)?.get(&nonce).copied()
+    }
+
+    /// Returns the nonce of the next transaction of a sender, following one
+    /// of the patterns of the mix
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The sender of the transaction.
+    /// * `mix` - The weights of the patterns.
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The nonce and the pattern it follows, so that the fees can be adjusted.
+    pub fn next(
+        &mut self,
+        address: Address,
+        mix: &NonceMix,
+        random: &mut StdRng,
+    ) -> (u64, NoncePattern) {
+        let pattern = mix.pick(random);
+        let pending = self.pending.entry(address).or_insert(0);
+
+        let nonce = match pattern {
+            NoncePattern::Sequential => {
+                *pending = pending.saturating_add(1);
+                *pending - 1
+            }
+            NoncePattern::Gap => pending.saturating_add(random.random_range(1..=MAX_NONCE_GAP)),
+            NoncePattern::Replacement | NoncePattern::Underpriced => pending.saturating_sub(1),
+            NoncePattern::Max => u64::MAX,
+        };
+
+        (nonce, pattern)
+    }
+}
+
+#[test]
+fn test_nonce_manager() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let mut nonces = NonceManager::default();
+    nonces.track(Address::ZERO, 7);
+
+    let mix = "sequential".parse::<NonceMix>().unwrap();
+    assert_eq!(nonces.next(Address::ZERO, &mix, &mut random), (7, NoncePattern::Sequential));
+    assert_eq!(nonces.next(Address::ZERO, &mix, &mut random), (8, NoncePattern::Sequential));
+
+    let mix = "replacement:1,max:0".parse::<NonceMix>().unwrap();
+    assert_eq!(mix.to_string(), "replacement:1");
+    assert_eq!(nonces.next(Address::ZERO, &mix, &mut random), (8, NoncePattern::Replacement));
+
+    let fees = Fees { gas_price: 121, max_fee_per_gas: 121, ..Default::default() };
+    nonces.record(Address::ZERO, 8, NoncePattern::Sequential, fees);
+    let bumped = NoncePattern::Replacement.adjust_fees(fees, &mut random).unwrap();
+    assert!(bumped.gas_price * 100 >= fees.gas_price * 110);
+    assert!(bumped.max_fee_per_gas * 100 >= fees.max_fee_per_gas * 110);
+    let underpriced = NoncePattern::Underpriced.adjust_fees(fees, &mut random).unwrap();
+    assert!(underpriced.gas_price * 100 < fees.gas_price * 110);
+    assert_eq!(nonces.fees(Address::ZERO, 8), Some(fees));
+
+    let blob = Fees { max_fee_per_blob_gas: 3, ..fees };
+    let bumped = NoncePattern::Replacement.adjust_fees(blob, &mut random).unwrap();
+    assert!(bumped.max_fee_per_blob_gas >= 6 && bumped.max_fee_per_gas >= 242);
+
+    nonces.sync(Address::ZERO, 20);
+    assert_eq!(nonces.fees(Address::ZERO, 8), None);
+    let mix = "max".parse::<NonceMix>().unwrap();
+    assert_eq!(nonces.next(Address::ZERO, &mix, &mut random).0, u64::MAX);
+    assert!("gap:0".parse::<NonceMix>().is_err());
+}
@@ -0,0 +1,115 @@
+use alloy::transports::TransportResult;
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{Interval, MissedTickBehavior, interval},
+};
+
+/// A response received from the node, together with the payload of the
+/// request it answers
+pub struct Response {
+    /// The position of the request in the order requests were sent
+    pub index: u64,
+    pub payload: Vec<u8>,
+    pub result: TransportResult<()>,
+}
+
+/// Pipeline keeps up to `in_flight` requests waiting for a response at the same
+/// time, so that the runner does not wait for the round-trip of every request
+/// before generating the next one. Optionally, it limits the number of
+/// requests sent per second.
+pub struct Pipeline {
+    /// The free slots of the in-flight window
+    semaphore: Arc<Semaphore>,
+    /// The requests waiting for a response, which yield their index
+    requests: JoinSet<(u64, TransportResult<()>)>,
+    /// The payloads of the requests waiting for a response, by index
+    in_flight: BTreeMap<u64, Vec<u8>>,
+    /// The number of requests sent so far
+    sent: u64,
+    /// The rate limiter, if any
+    interval: Option<Interval>,
+}
+
+impl Pipeline {
+    /// Creates a new `Pipeline` with the given in-flight window and rate
+    /// limit. A `max_tps` of 0 means no limit.
+    pub fn new(in_flight: usize, max_tps: u64) -> Self {
+        let interval = (max_tps > 0).then(|| {
+            let mut interval = interval(Duration::from_secs_f64(1.0 / max_tps as f64));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+
+        Self {
+            semaphore: Arc::new(Semaphore::new(in_flight)),
+            requests: JoinSet::new(),
+            in_flight: BTreeMap::new(),
+            sent: 0,
+            interval,
+        }
+    }
+
+    /// Sends a request, waiting for the rate limit and for a free slot in the
+    /// in-flight window first. The `payload` is returned with the response, so
+    /// that it can be matched with it.
+    pub async fn submit<F>(&mut self, payload: Vec<u8>, request: F)
+    where
+        F: Future<Output = TransportResult<()>> + Send + 'static,
+    {
+        if let Some(interval) = &mut self.interval {
+            interval.tick().await;
+        }
+
+        let permit = self.semaphore.clone().acquire_owned().await.unwrap();
+        let index = self.sent;
+        self.sent += 1;
+        self.in_flight.insert(index, payload);
+        self.requests.spawn(async move {
+            let result = request.await;
+            drop(permit);
+            (index, result)
+        });
+    }
+
+    /// Returns the responses received so far
+    pub fn completed(&mut self) -> Vec<Response> {
+        let mut responses = vec![];
+        while let Some(response) = self.requests.try_join_next() {
+            if let Ok((index, result)) = response {
+                responses.push(self.response(index, result));
+            }
+        }
+        responses
+    }
+
+    /// Waits for the responses to all the requests still in flight
+    pub async fn drain(&mut self) -> Vec<Response> {
+        let mut responses = vec![];
+        while let Some(response) = self.requests.join_next().await {
+            if let Ok((index, result)) = response {
+                responses.push(self.response(index, result));
+            }
+        }
+        responses
+    }
+
+    /// Returns the index and payload of the requests still waiting for a
+    /// response, in the order they were sent
+    pub fn in_flight(&self) -> impl Iterator<Item = (u64, &Vec<u8>)> {
+        self.in_flight.iter().map(|(index, payload)| (*index, payload))
+    }
+
+    /// Drops all the requests still in flight
+    pub fn abort(&mut self) {
+        self.requests.abort_all();
+        self.in_flight.clear();
+    }
+
+    // Helper function to match a result with the payload of its request
+    fn response(&mut self, index: u64, result: TransportResult<()>) -> Response {
+        let payload = self.in_flight.remove(&index).unwrap_or_default();
+        Response { index, payload, result }
+    }
+}
@@ -0,0 +1,726 @@
+use crate::signing::SECP256K1N;
+use alloy::{
+    eips::eip4844::{BYTES_PER_BLOB, c_kzg, env_settings::EnvKzgSettings, kzg_to_versioned_hash},
+    hex,
+    primitives::{Address, B256, U256},
+    signers::{SignerSync, local::PrivateKeySigner},
+};
+use common::{
+    constants::{MAX_PRECOMPILE_PAIRS, STATIC_KEYS},
+    parse_sk,
+};
+use rand::{Rng, RngCore, rngs::StdRng, seq::IndexedRandom};
+
+/// The precompiles up to Prague, plus P256VERIFY
+pub const PRECOMPILES: [u64; 18] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+    0x11, 0x100,
+];
+
+/// The base field modulus of BLS12-381, which `bls_element` pads to 64 bytes
+/// as in EIP-2537
+const BLS_MODULUS: [u8; 48] = hex!(
+    "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab"
+);
+
+/// The order of the groups of BLS12-381, which the scalars of the
+/// multi-scalar multiplications are taken modulo
+const BLS_ORDER: U256 =
+    U256::from_be_bytes(hex!("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"));
+
+/// The generator of G1 of BLS12-381, and its negation
+const BLS_G1: [[u8; 48]; 3] = [
+    hex!(
+        "17f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+    ),
+    hex!(
+        "08b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1"
+    ),
+    hex!(
+        "114d1d6855d545a8aa7d76c8cf2e21f267816aef1db507c96655b9d5caac42364e6f38ba0ecb751bad54dcd6b939c2ca"
+    ),
+];
+
+/// The generator of G2 of BLS12-381, as `x.c0, x.c1, y.c0, y.c1`
+const BLS_G2: [[u8; 48]; 4] = [
+    hex!(
+        "024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"
+    ),
+    hex!(
+        "13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e"
+    ),
+    hex!(
+        "0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801"
+    ),
+    hex!(
+        "0606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be"
+    ),
+];
+
+/// The generator of G2 of bn254, as encoded in EIP-197
+const BN254_G2: [u8; 128] = hex!(
+    "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"
+    "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed"
+    "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b"
+    "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa"
+);
+
+/// A point in affine coordinates, or `None` for the point at infinity
+type Point = Option<(U256, U256)>;
+
+/// Curve is a short Weierstrass curve `y^2 = x^3 + ax + b` over a prime field
+/// that fits in 256 bits, which covers bn254 and secp256r1
+struct Curve {
+    p: U256,
+    a: U256,
+    b: U256,
+    n: U256,
+    g: (U256, U256),
+}
+
+impl Curve {
+    fn bn254() -> Self {
+        Self {
+            p: U256::from_be_slice(&hex!(
+                "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47"
+            )),
+            a: U256::ZERO,
+            b: U256::from(3),
+            n: U256::from_be_slice(&hex!(
+                "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001"
+            )),
+            g: (U256::from(1), U256::from(2)),
+        }
+    }
+
+    fn secp256r1() -> Self {
+        let p = U256::from_be_slice(&hex!(
+            "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"
+        ));
+        Self {
+            p,
+            a: p - U256::from(3),
+            b: U256::from_be_slice(&hex!(
+                "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"
+            )),
+            n: U256::from_be_slice(&hex!(
+                "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"
+            )),
+            g: (
+                U256::from_be_slice(&hex!(
+                    "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"
+                )),
+                U256::from_be_slice(&hex!(
+                    "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"
+                )),
+            ),
+        }
+    }
+
+    fn is_on_curve(&self, (x, y): (U256, U256)) -> bool {
+        let p = self.p;
+        let rhs = x.mul_mod(x, p).mul_mod(x, p).add_mod(self.a.mul_mod(x, p), p).add_mod(self.b, p);
+        y.mul_mod(y, p) == rhs
+    }
+
+    fn add(&self, a: Point, b: Point) -> Point {
+        let ((x1, y1), (x2, y2)) = match (a, b) {
+            (None, b) => return b,
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let p = self.p;
+
+        let lambda = if x1 == x2 {
+            if y1.add_mod(y2, p).is_zero() {
+                return None;
+            }
+            let numerator = U256::from(3).mul_mod(x1.mul_mod(x1, p), p).add_mod(self.a, p);
+            numerator.mul_mod(y1.add_mod(y1, p).inv_mod(p)?, p)
+        } else {
+            y2.add_mod(p - y1, p).mul_mod(x2.add_mod(p - x1, p).inv_mod(p)?, p)
+        };
+
+        let x3 = lambda.mul_mod(lambda, p).add_mod(p - x1, p).add_mod(p - x2, p);
+        let y3 = lambda.mul_mod(x1.add_mod(p - x3, p), p).add_mod(p - y1, p);
+        Some((x3, y3))
+    }
+
+    fn mul(&self, point: Point, scalar: U256) -> Point {
+        let mut result = None;
+        let mut addend = point;
+        for i in 0..256 {
+            if scalar.bit(i) {
+                result = self.add(result, addend);
+            }
+            addend = self.add(addend, addend);
+        }
+        result
+    }
+
+    // Helper function to pick a random scalar in `[1, n)`
+    fn scalar(&self, random: &mut StdRng) -> U256 {
+        U256::from_be_bytes(random.random::<[u8; 32]>()).reduce_mod(self.n - U256::from(1))
+            + U256::from(1)
+    }
+
+    // Helper function to pick a point, valid most of the time
+    fn point(&self, random: &mut StdRng) -> Point {
+        Some(match random.random_range(0..10) {
+            // The point at infinity, encoded as zeros
+            0 => return None,
+            // Off the curve
+            1 => {
+                let (x, y) = self.mul(Some(self.g), self.scalar(random))?;
+                (x, y.add_mod(U256::from(1), self.p))
+            }
+            // Coordinates above the modulus
+            2 => {
+                let (x, y) = self.mul(Some(self.g), self.scalar(random))?;
+                (x.saturating_add(self.p), y)
+            }
+            _ => self.mul(Some(self.g), self.scalar(random))?,
+        })
+    }
+}
+
+/// Returns the address of a precompile
+pub fn address(precompile: u64) -> Address {
+    Address::from_word(B256::from(U256::from(precompile)))
+}
+
+/// Returns the precompile at the given address, if any
+pub fn precompile(address: &Address) -> Option<u64> {
+    let word = U256::from_be_slice(address.as_slice());
+    PRECOMPILES.iter().copied().find(|precompile| U256::from(*precompile) == word)
+}
+
+/// Generates the input of a call to a precompile, built around its format:
+/// valid points and signatures most of the time, and points off the curve,
+/// non-canonical field elements, huge lengths and wrong sizes otherwise
+///
+/// # Arguments
+///
+/// * `precompile` - One of `PRECOMPILES`.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The calldata of the call.
+pub fn input(precompile: u64, random: &mut StdRng) -> Vec<u8> {
+    let mut input = match precompile {
+        0x01 => ecrecover(random),
+        // sha256, ripemd160 and identity take any input, so only its length
+        // matters, around the block sizes of the hashes
+        0x02..=0x04 => {
+            let length = *[
+                0, 1, 31, 32, 55, 56, 63, 64, 65, 128, 4096,
+            ]
+            .choose(random)
+            .unwrap();
+            let mut input = vec![0u8; length];
+            random.fill_bytes(&mut input);
+            input
+        }
+        0x05 => modexp(random),
+        0x06 => {
+            let curve = Curve::bn254();
+            [
+                bn254_point(&curve, random),
+                bn254_point(&curve, random),
+            ]
+            .concat()
+        }
+        0x07 => {
+            let curve = Curve::bn254();
+            [
+                bn254_point(&curve, random),
+                edge_scalar(curve.n, random).to_be_bytes_vec(),
+            ]
+            .concat()
+        }
+        0x08 => bn254_pairing(random),
+        0x09 => blake2f(random),
+        0x0a => point_evaluation(random),
+        0x0b => [
+            bls_g1(random),
+            bls_g1(random),
+        ]
+        .concat(),
+        0x0c => bls_msm(bls_g1, random),
+        0x0d => [
+            bls_g2(random),
+            bls_g2(random),
+        ]
+        .concat(),
+        0x0e => bls_msm(bls_g2, random),
+        0x0f => bls_pairing(random),
+        0x10 => bls_fp(random),
+        0x11 => [
+            bls_fp(random),
+            bls_fp(random),
+        ]
+        .concat(),
+        _ => p256_verify(random),
+    };
+
+    // 10% chance to get the length wrong by a few bytes
+    if random.random_bool(0.1) {
+        if random.random_bool(0.5) && !input.is_empty() {
+            input.truncate(input.len() - random.random_range(1..=input.len().min(32)));
+        } else {
+            input.extend((0..random.random_range(1..=32)).map(|_| random.random::<u8>()));
+        }
+    }
+
+    input
+}
+
+// Helper function to build the input of ecrecover, out of a signature by one
+// of the static keys
+fn ecrecover(random: &mut StdRng) -> Vec<u8> {
+    let hash = B256::from(random.random::<[u8; 32]>());
+    let signer = PrivateKeySigner::from(parse_sk(STATIC_KEYS.choose(random).unwrap()).unwrap());
+    let Ok(signature) = signer.sign_hash_sync(&hash) else {
+        return hash.to_vec();
+    };
+
+    let mut v = U256::from(27 + signature.v() as u64);
+    let mut r = signature.r();
+    let mut s = signature.s();
+    match random.random_range(0..10) {
+        0 => {
+            v = U256::from(
+                *[
+                    0u64, 1, 29, 35, 36,
+                ]
+                .choose(random)
+                .unwrap(),
+            )
+        }
+        1 => s = SECP256K1N - s,
+        2 => r = U256::ZERO,
+        3 => r = SECP256K1N + r.reduce_mod(U256::MAX - SECP256K1N),
+        4 => v = U256::from(27) + (U256::from(1) << 255),
+        _ => {}
+    }
+
+    [
+        hash.to_vec(),
+        v.to_be_bytes_vec(),
+        r.to_be_bytes_vec(),
+        s.to_be_bytes_vec(),
+    ]
+    .concat()
+}
+
+// Helper function to build the input of modexp, with lengths that do not
+// match the data, huge exponent lengths and zero or even moduli
+fn modexp(random: &mut StdRng) -> Vec<u8> {
+    let length = |random: &mut StdRng| match random.random_range(0..10) {
+        0 => U256::ZERO,
+        // Above the 1024 bytes EIP-7823 caps the lengths to
+        1 => U256::from(random.random_range(1025..=4096)),
+        2 => U256::MAX,
+        3 => U256::from(u64::MAX),
+        _ => U256::from(random.random_range(1..=64)),
+    };
+    let (base, exponent, modulus) = (length(random), length(random), length(random));
+
+    let mut input = [
+        base.to_be_bytes_vec(),
+        exponent.to_be_bytes_vec(),
+        modulus.to_be_bytes_vec(),
+    ]
+    .concat();
+
+    // The data is as long as the lengths say, unless they are too large to
+    // fit, in which case the precompile pads it with zeros
+    for length in [
+        base, exponent, modulus,
+    ] {
+        let length =
+            if length > U256::from(4096) { random.random_range(0..64) } else { length.to() };
+        let mut data = vec![0u8; length];
+        random.fill_bytes(&mut data);
+
+        // 30% chance for an exponent or modulus full of leading zeros
+        if random.random_bool(0.3) {
+            let zeros = random.random_range(0..=data.len());
+            data[..zeros].fill(0);
+        }
+        input.extend_from_slice(&data);
+    }
+
+    input
+}
+
+// Helper function to encode a point of bn254
+fn bn254_point(curve: &Curve, random: &mut StdRng) -> Vec<u8> {
+    let (x, y) = curve.point(random).unwrap_or_default();
+    [
+        x.to_be_bytes_vec(),
+        y.to_be_bytes_vec(),
+    ]
+    .concat()
+}
+
+// Helper function to build pairs of bn254 points whose pairing is one, with
+// some of them replaced by junk
+fn bn254_pairing(random: &mut StdRng) -> Vec<u8> {
+    let curve = Curve::bn254();
+    let mut input = vec![];
+
+    for _ in 0..random.random_range(0..=MAX_PRECOMPILE_PAIRS / 2) {
+        // e(P, G2) * e(-P, G2) == 1
+        let (x, y) = curve.mul(Some(curve.g), curve.scalar(random)).unwrap_or_default();
+        for y in [
+            y,
+            curve.p - y,
+        ] {
+            let point = if random.random_bool(0.9) {
+                [
+                    x.to_be_bytes_vec(),
+                    y.to_be_bytes_vec(),
+                ]
+                .concat()
+            } else {
+                bn254_point(&curve, random)
+            };
+            let g2 = if random.random_bool(0.9) {
+                BN254_G2.to_vec()
+            } else {
+                (0..128).map(|_| random.random::<u8>()).collect()
+            };
+            input.extend_from_slice(&point);
+            input.extend_from_slice(&g2);
+        }
+    }
+
+    input
+}
+
+// Helper function to pick a scalar at the edges of the group order
+fn edge_scalar(n: U256, random: &mut StdRng) -> U256 {
+    match random.random_range(0..9) {
+        0 => U256::ZERO,
+        1 => n,
+        2 => n - U256::from(1),
+        3 => n.saturating_add(U256::from(1)),
+        4 => U256::MAX,
+        _ => U256::from_be_bytes(random.random::<[u8; 32]>()),
+    }
+}
+
+// Helper function to build the input of blake2f, with rounds near u32::MAX
+// and final block flags other than 0 and 1
+fn blake2f(random: &mut StdRng) -> Vec<u8> {
+    let rounds: u32 = match random.random_range(0..6) {
+        0 => 0,
+        1 => 12,
+        2 => u32::MAX,
+        3 => u32::MAX - random.random_range(1..1024),
+        _ => random.random_range(0..1024),
+    };
+    let flag = if random.random_bool(0.9) { random.random_range(0..=1) } else { random.random() };
+
+    let mut input = rounds.to_be_bytes().to_vec();
+    // h, m and t
+    input.extend((0..64 + 128 + 16).map(|_| random.random::<u8>()));
+    input.push(flag);
+    input
+}
+
+// Helper function to build the input of the point evaluation precompile,
+// with a valid proof most of the time and one broken field otherwise
+fn point_evaluation(random: &mut StdRng) -> Vec<u8> {
+    // Field elements are big endian, so a zero first byte keeps them below
+    // the BLS modulus
+    let mut z = random.random::<[u8; 32]>();
+    z[0] = 0;
+
+    // The zero polynomial, committed to with the point at infinity, is zero
+    // everywhere and has the point at infinity as its proof
+    let infinity = {
+        let mut point = [0u8; 48];
+        point[0] = 0xc0;
+        point
+    };
+    let (mut commitment, mut y, mut proof) = (infinity, [0u8; 32], infinity);
+
+    // 30% chance to evaluate a random polynomial instead
+    if random.random_bool(0.3) {
+        let mut blob = vec![0u8; BYTES_PER_BLOB];
+        random.fill_bytes(&mut blob);
+        for element in blob.chunks_mut(32) {
+            element[0] = 0;
+        }
+
+        let settings = EnvKzgSettings::Default.get();
+        if let Ok(blob) = c_kzg::Blob::from_bytes(&blob) {
+            if let (Ok(c), Ok((p, v))) = (
+                settings.blob_to_kzg_commitment(&blob),
+                settings.compute_kzg_proof(&blob, &c_kzg::Bytes32::new(z)),
+            ) {
+                commitment = c.to_bytes().into_inner();
+                proof = p.to_bytes().into_inner();
+                y.copy_from_slice(&v[..]);
+            }
+        }
+    }
+
+    let mut versioned_hash = kzg_to_versioned_hash(&commitment);
+    match random.random_range(0..10) {
+        // A versioned hash with a version other than 1
+        0 => versioned_hash[0] = random.random_range(2..=u8::MAX),
+        // An evaluation point above the BLS modulus
+        1 => z = [0xff; 32],
+        // A wrong evaluation
+        2 => y[31] ^= 1,
+        // A commitment that is not a point
+        3 => commitment[0] = 0xff,
+        // A proof of another evaluation, the compressed generator of G1,
+        // which is neither the proof of the zero polynomial nor likely the
+        // one of a random polynomial
+        4 => {
+            proof = BLS_G1[0];
+            proof[0] |= 0x80;
+        }
+        _ => {}
+    }
+
+    [
+        versioned_hash.as_slice(),
+        &z,
+        &y,
+        &commitment,
+        &proof,
+    ]
+    .concat()
+}
+
+// Helper function to encode a field element of BLS12-381 in 64 bytes, with
+// non-zero padding or the modulus itself from time to time
+fn bls_element(element: &[u8; 48], random: &mut StdRng) -> Vec<u8> {
+    let mut encoded = vec![0u8; 16];
+    match random.random_range(0..20) {
+        0 => encoded[random.random_range(0..16)] = random.random_range(1..=u8::MAX),
+        1 => {
+            encoded.extend_from_slice(&BLS_MODULUS);
+            return encoded;
+        }
+        _ => {}
+    }
+    encoded.extend_from_slice(element);
+    encoded
+}
+
+// Helper function to encode a point of G1 of BLS12-381
+fn bls_g1(random: &mut StdRng) -> Vec<u8> {
+    match random.random_range(0..10) {
+        // The point at infinity
+        0 => vec![0u8; 128],
+        // Off the curve
+        1 => {
+            let mut y = BLS_G1[1];
+            y[47] ^= 1;
+            [
+                bls_element(&BLS_G1[0], random),
+                bls_element(&y, random),
+            ]
+            .concat()
+        }
+        // The generator or its negation
+        _ => {
+            let y = if random.random_bool(0.5) { &BLS_G1[1] } else { &BLS_G1[2] };
+            [
+                bls_element(&BLS_G1[0], random),
+                bls_element(y, random),
+            ]
+            .concat()
+        }
+    }
+}
+
+// Helper function to encode a point of G2 of BLS12-381
+fn bls_g2(random: &mut StdRng) -> Vec<u8> {
+    match random.random_range(0..10) {
+        // The point at infinity
+        0 => vec![0u8; 256],
+        // Off the curve
+        1 => {
+            let mut point = BLS_G2;
+            point[3][47] ^= 1;
+            point.iter().flat_map(|element| bls_element(element, random)).collect()
+        }
+        _ => BLS_G2.iter().flat_map(|element| bls_element(element, random)).collect(),
+    }
+}
+
+// Helper function to build the input of a multi-scalar multiplication, which
+// may have no pairs at all, as the empty input is one the precompiles reject
+fn bls_msm(point: fn(&mut StdRng) -> Vec<u8>, random: &mut StdRng) -> Vec<u8> {
+    let mut input = vec![];
+    for _ in 0..random.random_range(0..=MAX_PRECOMPILE_PAIRS) {
+        input.extend_from_slice(&point(random));
+        input.extend_from_slice(&edge_scalar(BLS_ORDER, random).to_be_bytes_vec());
+    }
+    input
+}
+
+// Helper function to build pairs of BLS12-381 points whose pairing is one,
+// as e(G1, G2) * e(-G1, G2) is, with some of them off the curve
+fn bls_pairing(random: &mut StdRng) -> Vec<u8> {
+    let mut input = vec![];
+    for _ in 0..random.random_range(0..=MAX_PRECOMPILE_PAIRS / 2) {
+        for y in [
+            &BLS_G1[1], &BLS_G1[2],
+        ] {
+            let g1 = if random.random_bool(0.9) {
+                [
+                    bls_element(&BLS_G1[0], random),
+                    bls_element(y, random),
+                ]
+                .concat()
+            } else {
+                bls_g1(random)
+            };
+            input.extend_from_slice(&g1);
+            input.extend_from_slice(&bls_g2(random));
+        }
+    }
+    input
+}
+
+// Helper function to encode a random field element of BLS12-381, which the
+// map precompiles take
+fn bls_fp(random: &mut StdRng) -> Vec<u8> {
+    let mut element = random.random::<[u8; 48]>();
+    // Below the modulus, as its first byte is 0x1a
+    element[0] %= 0x1a;
+    bls_element(&element, random)
+}
+
+/// Signs a random hash with a random key on secp256r1
+///
+/// # Arguments
+///
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The words of a valid input of P256VERIFY: the hash, `r`, `s` and the
+/// coordinates of the public key.
+pub fn p256_signature(random: &mut StdRng) -> [U256; 5] {
+    let curve = Curve::secp256r1();
+    let hash = U256::from_be_bytes(random.random::<[u8; 32]>());
+
+    let d = curve.scalar(random);
+    let k = curve.scalar(random);
+    let (qx, qy) = curve.mul(Some(curve.g), d).unwrap_or_default();
+    debug_assert!(curve.is_on_curve((qx, qy)));
+    let (rx, _) = curve.mul(Some(curve.g), k).unwrap_or_default();
+
+    // r = R.x mod n, s = k^-1 (h + r d) mod n
+    let n = curve.n;
+    let r = rx.reduce_mod(n);
+    let s = k.inv_mod(n).unwrap_or_default().mul_mod(hash.add_mod(r.mul_mod(d, n), n), n);
+
+    [
+        hash, r, s, qx, qy,
+    ]
+}
+
+// Helper function to build the input of P256VERIFY, out of a signature by a
+// random key on secp256r1
+fn p256_verify(random: &mut StdRng) -> Vec<u8> {
+    let curve = Curve::secp256r1();
+    let [
+        hash,
+        mut r,
+        mut s,
+        mut qx,
+        mut qy,
+    ] = p256_signature(random);
+    let n = curve.n;
+
+    match random.random_range(0..10) {
+        // The other valid s, which P256VERIFY accepts too
+        0 => s = n - s,
+        1 => r = U256::ZERO,
+        2 => s = n,
+        // A public key off the curve
+        3 => qy = qy.add_mod(U256::from(1), curve.p),
+        // The point at infinity as the public key
+        4 => (qx, qy) = (U256::ZERO, U256::ZERO),
+        _ => {}
+    }
+
+    [
+        hash, r, s, qx, qy,
+    ]
+    .iter()
+    .flat_map(|word| word.to_be_bytes_vec())
+    .collect()
+}
+
+#[test]
+fn test_precompile_curves() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    for curve in [
+        Curve::bn254(),
+        Curve::secp256r1(),
+    ] {
+        assert!(curve.is_on_curve(curve.g));
+        assert_eq!(curve.mul(Some(curve.g), curve.n), None);
+
+        let point = curve.mul(Some(curve.g), curve.scalar(&mut random)).unwrap();
+        assert!(curve.is_on_curve(point));
+    }
+
+    assert_eq!(precompile(&address(0x100)), Some(0x100));
+    // u1 G + u2 Q has r as its x coordinate, with u1 = h / s and u2 = r / s
+    let curve = Curve::secp256r1();
+    let [
+        hash,
+        r,
+        s,
+        qx,
+        qy,
+    ] = p256_signature(&mut random);
+    let w = s.inv_mod(curve.n).unwrap();
+    let u1 = curve.mul(Some(curve.g), hash.mul_mod(w, curve.n));
+    let u2 = curve.mul(Some((qx, qy)), r.mul_mod(w, curve.n));
+    assert_eq!(curve.add(u1, u2).unwrap().0.reduce_mod(curve.n), r);
+
+    assert_eq!(blake2f(&mut random).len(), 213);
+    assert_eq!(point_evaluation(&mut random).len(), 192);
+    assert_eq!(bls_g2(&mut random).len(), 256);
+    assert_eq!(p256_verify(&mut random).len(), 160);
+}
+
+#[test]
+fn test_point_evaluation_proofs() {
+    let settings = EnvKzgSettings::Default.get();
+    let zero = c_kzg::Bytes32::new([0u8; 32]);
+    let mut infinity = [0u8; 48];
+    infinity[0] = 0xc0;
+    let mut generator = BLS_G1[0];
+    generator[0] |= 0x80;
+
+    // The zero polynomial has the point at infinity as its proof, and the
+    // generator is a valid point that does not prove it
+    let commitment = c_kzg::Bytes48::new(infinity);
+    assert!(
+        settings
+            .verify_kzg_proof(&commitment, &zero, &zero, &c_kzg::Bytes48::new(infinity))
+            .unwrap()
+    );
+    assert!(
+        !settings
+            .verify_kzg_proof(&commitment, &zero, &zero, &c_kzg::Bytes48::new(generator))
+            .unwrap()
+    );
+}
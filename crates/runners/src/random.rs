@@ -1,135 +1,45 @@
-use crate::{builder::Builder, cache::BuilderCache, logger::Logger};
+use crate::{
+    Runner,
+    builder::Builder,
+    signing,
+    transaction::{Transaction, TransactionRunner},
+};
 use alloy::{
-    consensus::{
-        TxEip1559, TxEip2930, TxEip4844, TxEip4844WithSidecar, TxEip7702, TxLegacy,
-        transaction::RlpEcdsaEncodableTx,
-    },
-    primitives::{Address, TxHash},
-    providers::{Provider, ProviderBuilder},
+    consensus::{TxEip1559, TxEip2930, TxEip4844, TxEip4844WithSidecar, TxEip7702, TxLegacy},
+    primitives::Address,
     rpc::types::TransactionRequest,
-    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
-    transports::http::reqwest::Url,
+    signers::k256::ecdsa::SigningKey,
 };
-use alloy_rlp::Encodable;
-use common::{constants::MAX_TRANSACTION_LENGTH, types::Backend};
-use mutator::Mutator;
-use rand::{Rng, SeedableRng, random_bool, rngs::StdRng};
-pub struct RandomTransactionRunner {
-    pub sk: SigningKey,
-    pub seed: u64,
-    pub provider: Backend,
-    pub current_tx: Vec<u8>,
-    pub mutator: Mutator,
-    pub running: bool,
-    pub crash_counter: u64,
-    pub logger: Logger,
-    pub cache: BuilderCache,
-    pub sender: Address,
-}
+use common::constants::MAX_TRANSACTION_LENGTH;
+use rand::{Rng, rngs::StdRng};
 
-impl Builder for RandomTransactionRunner {
-    fn provider(&self) -> &Backend {
-        &self.provider
-    }
+/// Random is the transaction of the random runner, of a random type and with
+/// every field filled whether the type has it or not
+pub struct Random;
 
-    fn cache(&self) -> &BuilderCache {
-        &self.cache
-    }
+pub type RandomTransactionRunner = TransactionRunner<Random>;
 
-    fn cache_mut(&mut self) -> &mut BuilderCache {
-        &mut self.cache
-    }
-}
+impl Transaction for Random {
+    const RUNNER: Runner = Runner::Random;
 
-impl RandomTransactionRunner {
-    pub async fn new(
-        rpc_url: Url,
-        sk: SigningKey,
-        seed: u64,
-        max_operations_per_mutation: u64,
-    ) -> Self {
-        let provider = ProviderBuilder::new()
-            .wallet::<PrivateKeySigner>(sk.clone().into())
-            .connect_http(rpc_url);
-
-        let sender = Address::from_private_key(&sk);
-        let account = provider.get_account(sender).await.unwrap_or_default();
-        let cache = BuilderCache {
-            gas_price: provider.get_gas_price().await.unwrap_or_default(),
-            max_priority_fee: provider.get_max_priority_fee_per_gas().await.unwrap_or_default(),
-            max_fee_per_blob_gas: provider.get_blob_base_fee().await.unwrap_or_default(),
-            balance: account.balance,
-            nonce: account.nonce,
-            chain_id: provider.get_chain_id().await.unwrap_or_default(),
-        };
-
-        let mutator = Mutator::new(max_operations_per_mutation, seed);
-        let logger = Logger::new("random").unwrap();
-
-        Self {
-            sk,
-            seed,
-            current_tx: vec![],
-            provider,
-            mutator,
-            running: false,
-            crash_counter: 0,
-            logger,
-            cache,
-            sender,
-        }
+    async fn create(
+        runner: &RandomTransactionRunner,
+        random: &mut StdRng,
+    ) -> (TransactionRequest, Vec<u8>) {
+        runner.create_random_transaction(random).await
     }
 
-    pub async fn run(&mut self) {
-        let mut random = StdRng::seed_from_u64(self.seed);
-        self.running = true;
-
-        loop {
-            // 10% chance to re-generate the transaction
-            if random_bool(0.1) || self.current_tx.is_empty() {
-                // 50% chance to update the cache
-                // This is to try to get further in the execution by bypassing
-                // common checks like gas > expected and so on
-                if random_bool(0.5) {
-                    self.cache.update(&self.provider, self.sender).await;
-                }
-
-                let (request, tx) = self.create_random_transaction(&mut random).await;
-                tx.encode(&mut self.current_tx);
-
-                if let Err(err) = self.provider.send_transaction_unsafe(request).await {
-                    if self.logger.is_connection_refused_error(&err) {
-                        let current_tx = self.current_tx.clone();
-                        let _ = self.logger.generate_crash_report(&current_tx);
-
-                        self.crash_counter += 1;
-                        self.running = false;
-
-                        break;
-                    }
-                }
-            } else {
-                self.mutator.mutate(&mut self.current_tx);
-                if let Err(err) = self
-                    .provider
-                    .client()
-                    .request::<_, TxHash>("eth_sendRawTransaction", &self.current_tx)
-                    .await
-                {
-                    if self.logger.is_connection_refused_error(&err) {
-                        let current_tx = self.current_tx.clone();
-                        let _ = self.logger.generate_crash_report(&current_tx);
-
-                        self.crash_counter += 1;
-                        self.running = false;
-
-                        break;
-                    }
-                }
-            }
-        }
+    fn sign_mutated(
+        signing: signing::Signing,
+        bytes: &[u8],
+        sk: &SigningKey,
+        random: &mut StdRng,
+    ) -> Option<Vec<u8>> {
+        signing::sign_mutated_any(signing, bytes, sk, random)
     }
+}
 
+impl RandomTransactionRunner {
     pub async fn create_random_transaction(
         &self,
         random: &mut StdRng,
@@ -137,27 +47,25 @@ impl RandomTransactionRunner {
         let to = self.to(random);
         let gas_price = self.gas_price(random).await;
         let max_fee_per_gas = self.max_fee_per_gas(random);
-        let max_priority_fee_per_gas = self.max_priority_fee_per_gas(random).await;
+        let max_priority_fee_per_gas = self.max_priority_fee_per_gas(max_fee_per_gas, random).await;
         let max_fee_per_blob_gas = self.max_fee_per_blob_gas(random).await;
-        let gas = self.gas(random);
         let value = self.value(random).await;
-        let input = self.input(random);
+        let input = self.input(&to, random);
         let nonce = self.nonce(random).await;
         let chain_id = self.chain_id(random).await;
         let access_list = self.access_list(random);
         let transaction_type = self.transaction_type(random);
-        let blob_versioned_hashes = self.blob_versioned_hashes(random);
-        let sidecar = self.sidecar(random);
-        let authorization_list = self.authorization_list(random);
+        let (blob_versioned_hashes, sidecar) = self.blobs(random);
+        let authorization_list = self.authorization_list(random).await;
 
-        let request = TransactionRequest {
+        let mut request = TransactionRequest {
             from: Some(self.sender),
             to: Some(to),
             gas_price: Some(gas_price),
             max_fee_per_gas: Some(max_fee_per_gas),
             max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
             max_fee_per_blob_gas: Some(max_fee_per_blob_gas),
-            gas: Some(gas),
+            gas: None,
             value: Some(value),
             input: input.clone(),
             nonce: Some(nonce),
@@ -169,20 +77,23 @@ impl RandomTransactionRunner {
             authorization_list: Some(authorization_list.clone()),
         };
 
+        let gas = self.gas(&request, random).await;
+        request.gas = Some(gas);
+
         let mut encoded = vec![];
         match transaction_type {
             0 => {
                 let tx = TxLegacy {
                     to,
                     value,
-                    chain_id: Some(chain_id),
+                    chain_id: self.config.eip155.then_some(chain_id),
                     nonce,
                     gas_price,
                     gas_limit: gas,
                     input: input.into_input().unwrap(),
                 };
 
-                tx.encode(&mut encoded);
+                encoded = signing::envelope(tx, &self.sk);
             }
             1 => {
                 let tx = TxEip2930 {
@@ -196,7 +107,7 @@ impl RandomTransactionRunner {
                     input: input.into_input().unwrap(),
                 };
 
-                tx.encode(&mut encoded);
+                encoded = signing::envelope(tx, &self.sk);
             }
             2 => {
                 let tx = TxEip1559 {
@@ -211,7 +122,7 @@ impl RandomTransactionRunner {
                     max_priority_fee_per_gas,
                 };
 
-                tx.encode(&mut encoded);
+                encoded = signing::envelope(tx, &self.sk);
             }
             3 => {
                 let tx = TxEip4844 {
@@ -230,7 +141,12 @@ impl RandomTransactionRunner {
 
                 let tx_with_sidecar = TxEip4844WithSidecar { tx, sidecar };
 
-                tx_with_sidecar.rlp_encode(&mut encoded);
+                encoded = signing::blob_envelope(
+                    tx_with_sidecar,
+                    self.fork(random).blob_form(self.config.blob_form),
+                    &self.sk,
+                    random,
+                );
             }
             4 => {
                 let tx = TxEip7702 {
@@ -246,7 +162,7 @@ impl RandomTransactionRunner {
                     input: input.into_input().unwrap(),
                 };
 
-                tx.encode(&mut encoded);
+                encoded = signing::envelope(tx, &self.sk);
             }
             _ => {
                 // Fill with random bytes for any other transaction type
@@ -265,8 +181,13 @@ impl RandomTransactionRunner {
 
 #[tokio::test]
 async fn test_random_transaction_runner() {
+    use crate::config::RunnerConfig;
+    use alloy::transports::http::reqwest::Url;
+    use rand::SeedableRng;
+
     let mut rng = StdRng::seed_from_u64(1);
     let runner = RandomTransactionRunner::new(
+        "random",
         "http://localhost:8545".parse::<Url>().unwrap(),
         SigningKey::from_slice(
             &alloy::hex::decode(
@@ -277,6 +198,7 @@ async fn test_random_transaction_runner() {
         .unwrap(),
         1,
         1000,
+        RunnerConfig::default(),
     )
     .await;
     let tx = runner.create_random_transaction(&mut rng).await;
@@ -0,0 +1,667 @@
+use crate::{
+    Runner,
+    builder::Builder,
+    cache::BuilderCache,
+    checkpoint::Checkpoint,
+    config::RunnerConfig,
+    control::{Outcome, Session, Worker},
+    fork::Fork,
+    nonces::Fees,
+    senders::SenderPool,
+    signing,
+};
+use alloy::{
+    consensus::{TxEip1559, TxEip4844, TxEip4844WithSidecar},
+    primitives::{Address, TxHash, U256},
+    providers::Provider,
+    signers::k256::ecdsa::SigningKey,
+    transports::http::reqwest::{Client, Url},
+};
+use common::{
+    connect,
+    constants::{
+        BLOB_SCENARIO_TRANSACTIONS, GAP_CHAIN_LENGTH, MAX_NONCE_GAP, POOL_FILL_TRANSACTIONS,
+        REPLACEMENT_STORM_LENGTH,
+    },
+    types::Backend,
+};
+use rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    mem,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The names of the scenarios, in the order they are run by default
+pub const SCENARIOS: [&str; 5] = [
+    "fill",
+    "eviction",
+    "replacement",
+    "gaps",
+    "blobs",
+];
+
+/// The metrics holding the memory used by the node, as exported by reth,
+/// geth and nethermind respectively
+const MEMORY_METRICS: [&str; 3] = [
+    "process_resident_memory_bytes",
+    "system_memory_used",
+    "process_working_set_bytes",
+];
+
+/// Scenario is a coordinated sequence of transactions that stresses one part
+/// of the transaction pool of the node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scenario {
+    /// Enough pending transactions to reach the global limits of the pool
+    Fill,
+    /// A full pool of cheap transactions, then as many expensive ones from
+    /// other senders, which have to evict them
+    Eviction,
+    /// Replace-by-fee storms on the same nonce, with bumps right at and right
+    /// below the minimum the pool asks for
+    Replacement,
+    /// Long chains of queued transactions behind a nonce gap, from every
+    /// sender at the same time
+    Gaps,
+    /// Blob transactions with blob fees below, at and above the blob base
+    /// fee, and replacements with and without the bump blob pools ask for
+    Blobs,
+}
+
+impl Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scenario::Fill => write!(f, "fill"),
+            Scenario::Eviction => write!(f, "eviction"),
+            Scenario::Replacement => write!(f, "replacement"),
+            Scenario::Gaps => write!(f, "gaps"),
+            Scenario::Blobs => write!(f, "blobs"),
+        }
+    }
+}
+
+impl FromStr for Scenario {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fill" => Ok(Scenario::Fill),
+            "eviction" => Ok(Scenario::Eviction),
+            "replacement" => Ok(Scenario::Replacement),
+            "gaps" => Ok(Scenario::Gaps),
+            "blobs" => Ok(Scenario::Blobs),
+            _ => Err(format!("invalid scenario: {}", s)),
+        }
+    }
+}
+
+/// PlannedTx is a transaction of a scenario, before it is built and signed.
+/// Fees are given in wei, so that bumps are exact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedTx {
+    /// The index of the sender in the pool
+    pub sender: usize,
+    pub nonce: u64,
+    pub fees: Fees,
+    pub blob: bool,
+}
+
+impl Scenario {
+    /// Plans the transactions of the scenario
+    ///
+    /// # Arguments
+    ///
+    /// * `nonces` - The pending nonce of each sender of the pool.
+    /// * `fees` - The fees of the node when the scenario starts, which the
+    ///   ones of the transactions are picked around.
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The transactions, in the order they have to be sent.
+    pub fn plan(&self, nonces: &[u64], fees: Fees, random: &mut StdRng) -> Vec<PlannedTx> {
+        let senders = nonces.len();
+        let mut plan = vec![];
+        if senders == 0 {
+            return plan;
+        }
+
+        let planned = |sender: usize, nonce: u64, percent: u128| PlannedTx {
+            sender,
+            nonce,
+            fees: fees.scale(percent),
+            blob: false,
+        };
+
+        match self {
+            Scenario::Fill => {
+                // Senders take turns, so that the pool fills evenly
+                let depth = POOL_FILL_TRANSACTIONS.div_ceil(senders) as u64;
+                for i in 0..depth {
+                    for (sender, nonce) in nonces.iter().enumerate() {
+                        plan.push(planned(sender, nonce + i, random.random_range(100..=120)));
+                    }
+                }
+            }
+            Scenario::Eviction => {
+                // Half of the senders fill the pool at the current price, and
+                // the other half come in paying several times more
+                let half = (senders / 2).max(1);
+                for (range, fee) in [
+                    (0..half, 100..=100),
+                    (half..senders, 200..=1000),
+                ] {
+                    let depth = POOL_FILL_TRANSACTIONS.div_ceil(range.len().max(1)) as u64;
+                    for i in 0..depth {
+                        for sender in range.clone() {
+                            plan.push(planned(
+                                sender,
+                                nonces[sender] + i,
+                                random.random_range(fee.clone()),
+                            ));
+                        }
+                    }
+                }
+            }
+            Scenario::Replacement => {
+                let sender = random.random_range(0..senders);
+                let mut accepted = planned(sender, nonces[sender], 100);
+                plan.push(accepted);
+
+                for _ in 1..REPLACEMENT_STORM_LENGTH {
+                    // The bumps are relative to the last replacement the pool
+                    // accepted, in wei, so that they do not drift below 10%
+                    let (fees, bumped) = match random.random_range(0..10) {
+                        // The same price again
+                        0 => (accepted.fees, false),
+                        // Right below the 10% bump
+                        1 => (accepted.fees.scale(109), false),
+                        // Underpriced
+                        2 => (accepted.fees.scale(random.random_range(50..100)), false),
+                        // Right at the 10% bump
+                        _ => (accepted.fees.bump(110), true),
+                    };
+
+                    let replacement = PlannedTx { fees, ..accepted };
+                    plan.push(replacement);
+                    if bumped {
+                        accepted = replacement;
+                    }
+                }
+            }
+            Scenario::Gaps => {
+                let gaps: Vec<u64> =
+                    (0..senders).map(|_| random.random_range(1..=MAX_NONCE_GAP)).collect();
+                for i in 0..GAP_CHAIN_LENGTH {
+                    for (sender, nonce) in nonces.iter().enumerate() {
+                        plan.push(planned(sender, nonce + gaps[sender] + i, 100));
+                    }
+                }
+            }
+            Scenario::Blobs => {
+                for i in 0..BLOB_SCENARIO_TRANSACTIONS {
+                    let sender = i % senders;
+                    let nonce = nonces[sender] + (i / senders) as u64;
+                    let blob_fee = match random.random_range(0..3) {
+                        // Below the blob base fee
+                        0 => random.random_range(50..100),
+                        // At the blob base fee
+                        1 => 100,
+                        // Above it
+                        _ => random.random_range(101..=1000),
+                    };
+                    let fees = Fees {
+                        max_fee_per_blob_gas: fees.max_fee_per_blob_gas.saturating_mul(blob_fee)
+                            / 100,
+                        ..fees
+                    };
+                    plan.push(PlannedTx { sender, nonce, fees, blob: true });
+
+                    // 25% chance to replace it right away, with the 100% bump
+                    // blob pools ask for or with only half of it
+                    if random.random_bool(0.25) {
+                        let bump = if random.random_bool(0.5) { 200 } else { 150 };
+                        plan.push(PlannedTx { sender, nonce, fees: fees.bump(bump), blob: true });
+                    }
+                }
+            }
+        }
+
+        plan
+    }
+}
+
+/// Report holds what is measured while a scenario runs: the state of the pool
+/// and the memory of the node before and after it, and the latency of every
+/// transaction sent
+pub struct Report {
+    pub scenario: Scenario,
+    pub started_at: Instant,
+    pub transactions: usize,
+    /// The pending and queued transactions of the pool when it started
+    pub pool: Option<(u64, u64)>,
+    /// The memory used by the node when it started, in bytes
+    pub memory: Option<u64>,
+    pub latencies: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl Report {
+    /// Summarizes the report, given the state of the pool and the memory of
+    /// the node once the scenario is over
+    pub fn summary(&self, pool: Option<(u64, u64)>, memory: Option<u64>) -> String {
+        let mut latencies = self.latencies.lock().unwrap().clone();
+        latencies.sort();
+
+        let percentile = |p: usize| {
+            latencies.get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+        };
+        let millis = |latency: Option<&Duration>| match latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "-".to_string(),
+        };
+        let status = |pool: Option<(u64, u64)>| match pool {
+            Some((pending, queued)) => format!("{}/{}", pending, queued),
+            None => "-".to_string(),
+        };
+        let growth = match (self.memory, memory) {
+            (Some(before), Some(after)) => {
+                format!("{:+}MiB", (after as i128 - before as i128) / (1024 * 1024))
+            }
+            _ => "-".to_string(),
+        };
+
+        format!(
+            "scenario {} sent {} transactions in {}s, pool (pending/queued) {} -> {}, memory {}, latency p50={} p99={} max={}",
+            self.scenario,
+            self.transactions,
+            self.started_at.elapsed().as_secs(),
+            status(self.pool),
+            status(pool),
+            growth,
+            millis(percentile(50)),
+            millis(percentile(99)),
+            millis(latencies.last()),
+        )
+    }
+}
+
+/// Parses the memory used by the node out of its Prometheus metrics
+pub fn parse_memory(metrics: &str) -> Option<u64> {
+    for metric in MEMORY_METRICS {
+        for line in metrics.lines().filter(|line| !line.starts_with('#')) {
+            let name = line
+                .split([
+                    '{', ' ',
+                ])
+                .next()
+                .unwrap_or_default();
+            if name.ends_with(metric) {
+                let value = line.split_whitespace().last()?.parse::<f64>().ok()?;
+                return Some(value as u64);
+            }
+        }
+    }
+
+    None
+}
+
+/// ScenarioRunner drives coordinated sequences of transactions against the
+/// transaction pool of the node instead of independent random ones: filling
+/// it to its limits, evicting from it, replacement storms, nonce-gap chains
+/// and blobs around the blob base fee. The transactions themselves are valid,
+/// as the point is to stress the pool and not its validation.
+pub struct ScenarioRunner {
+    pub session: Session,
+    pub sk: SigningKey,
+    pub provider: Backend,
+    pub client: Client,
+    pub cache: BuilderCache,
+    pub sender: Address,
+    pub config: RunnerConfig,
+    pub senders: SenderPool,
+    pub scenario: usize,
+    pub planned: VecDeque<PlannedTx>,
+    pub report: Option<Report>,
+}
+
+impl Builder for ScenarioRunner {
+    fn provider(&self) -> &Backend {
+        &self.provider
+    }
+
+    fn cache(&self) -> &BuilderCache {
+        &self.cache
+    }
+
+    fn cache_mut(&mut self) -> &mut BuilderCache {
+        &mut self.cache
+    }
+
+    fn sender(&self) -> Address {
+        self.sender
+    }
+
+    fn config(&self) -> &RunnerConfig {
+        &self.config
+    }
+}
+
+impl Worker for ScenarioRunner {
+    const RUNNER: Runner = Runner::Scenario;
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    async fn step(&mut self, random: &mut StdRng) -> Outcome {
+        if self.planned.is_empty() {
+            // The scenario is measured once all of its transactions have been
+            // answered
+            let responses = self.session.pipeline.drain().await;
+            if self.session.check(responses) {
+                return Outcome::Crashed;
+            }
+            self.finish_scenario().await;
+            self.start_scenario(random).await;
+        }
+
+        let Some(planned) = self.planned.pop_front() else {
+            return Outcome::Idle;
+        };
+        self.session.current_tx = self.create_scenario_transaction(planned, random);
+
+        let provider = self.provider.clone();
+        let raw = self.session.current_tx.clone();
+        let latencies = self.report.as_ref().map(|report| report.latencies.clone());
+        self.session
+            .pipeline
+            .submit(self.session.current_tx.clone(), async move {
+                let sent_at = Instant::now();
+                let result = provider
+                    .client()
+                    .request::<_, TxHash>("eth_sendRawTransaction", &raw)
+                    .await
+                    .map(|_| ());
+                if let Some(latencies) = latencies {
+                    latencies.lock().unwrap().push(sent_at.elapsed());
+                }
+                result
+            })
+            .await;
+
+        Outcome::Sent
+    }
+
+    async fn finish(&mut self) {
+        self.finish_scenario().await;
+    }
+
+    /// Checkpoints carry the scenario that is running and the transactions
+    /// it has left to send, so a restored runner picks it up where it was.
+    /// Its measurements start over, as the state of the node they compare
+    /// against is gone.
+    fn save(&self, checkpoint: &mut Checkpoint) {
+        checkpoint.cache = self.cache.clone();
+        checkpoint.scenario = self.scenario;
+        checkpoint.running_scenario = self.report.as_ref().map(|report| report.scenario);
+        checkpoint.planned = self.planned.iter().copied().collect();
+    }
+
+    fn load(&mut self, checkpoint: &mut Checkpoint) {
+        self.cache = mem::take(&mut checkpoint.cache);
+        self.scenario = checkpoint.scenario;
+        self.planned = mem::take(&mut checkpoint.planned).into();
+        self.report = checkpoint.running_scenario.map(|scenario| Report {
+            scenario,
+            started_at: Instant::now(),
+            transactions: self.planned.len(),
+            pool: None,
+            memory: None,
+            latencies: Arc::new(Mutex::new(vec![])),
+        });
+    }
+}
+
+impl ScenarioRunner {
+    pub async fn new(
+        name: &str,
+        rpc_url: Url,
+        sk: SigningKey,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        config: RunnerConfig,
+    ) -> Self {
+        // The transport is chosen by the scheme of the URL. The app checks the
+        // connection before spawning the runner, so this should not fail
+        let provider = connect(&rpc_url, &sk).await.unwrap();
+
+        let sender = Address::from_private_key(&sk);
+        let account = provider.get_account(sender).await.unwrap_or_default();
+        let mut cache = BuilderCache {
+            gas_price: provider.get_gas_price().await.unwrap_or_default(),
+            max_priority_fee: provider.get_max_priority_fee_per_gas().await.unwrap_or_default(),
+            max_fee_per_blob_gas: provider.get_blob_base_fee().await.unwrap_or_default(),
+            balance: account.balance,
+            nonce: account.nonce,
+            chain_id: provider.get_chain_id().await.unwrap_or_default(),
+            contracts: vec![],
+            ..Default::default()
+        };
+        cache.update_block(&provider).await;
+        cache.fork = Fork::resolve(config.fork, &provider).await;
+
+        let session = Session::new(name, seed, max_operations_per_mutation, &config);
+
+        let senders = SenderPool::new(
+            &provider,
+            config.sender_offset,
+            SenderPool::size(Runner::Scenario, &config),
+        )
+        .await;
+
+        Self {
+            session,
+            sk,
+            provider,
+            client: Client::new(),
+            cache,
+            sender,
+            config,
+            senders,
+            scenario: 0,
+            planned: VecDeque::new(),
+            report: None,
+        }
+    }
+
+    /// Starts the next scenario of the config: the fees and the pending nonce
+    /// of every sender are fetched, the state of the node is measured and its
+    /// transactions are planned
+    pub async fn start_scenario(&mut self, random: &mut StdRng) {
+        let scenarios = &self.config.scenarios;
+        if scenarios.is_empty() {
+            return;
+        }
+        let scenario = scenarios[self.scenario % scenarios.len()];
+        self.scenario = self.scenario.wrapping_add(1);
+
+        self.cache.update(&self.provider, self.sender).await;
+
+        let mut nonces = vec![];
+        for sender in self.senders.senders() {
+            let nonce = self
+                .provider
+                .get_transaction_count(sender.address)
+                .pending()
+                .await
+                .unwrap_or(sender.nonce);
+            nonces.push(nonce);
+        }
+
+        // The priority fee is at least one wei, so that bumps are never lost,
+        // and the maximum fee covers twice the gas price, as wallets do
+        let max_priority_fee_per_gas = self.cache.max_priority_fee.max(1);
+        let fees = Fees {
+            gas_price: self.cache.gas_price,
+            max_fee_per_gas: self.cache.gas_price.saturating_mul(2).max(max_priority_fee_per_gas),
+            max_priority_fee_per_gas,
+            max_fee_per_blob_gas: self.cache.max_fee_per_blob_gas.max(1),
+        };
+
+        self.planned = scenario.plan(&nonces, fees, random).into();
+        self.report = Some(Report {
+            scenario,
+            started_at: Instant::now(),
+            transactions: self.planned.len(),
+            pool: self.pool_status().await,
+            memory: self.memory().await,
+            latencies: Arc::new(Mutex::new(vec![])),
+        });
+    }
+
+    /// Logs the report of the scenario that was running, if any
+    pub async fn finish_scenario(&mut self) {
+        if let Some(report) = self.report.take() {
+            let summary = report.summary(self.pool_status().await, self.memory().await);
+            let _ = self.session.logger.log(&summary);
+        }
+    }
+
+    /// Builds and signs a planned transaction. They are plain transfers to
+    /// random accounts, with the fees planned.
+    pub fn create_scenario_transaction(&self, planned: PlannedTx, random: &mut StdRng) -> Vec<u8> {
+        let Some(sender) = self.senders.senders().get(planned.sender) else {
+            return vec![];
+        };
+
+        let Fees { max_fee_per_gas, max_priority_fee_per_gas, max_fee_per_blob_gas, .. } =
+            planned.fees;
+
+        if planned.blob {
+            let (blob_versioned_hashes, sidecar) = self.kzg_blobs(random);
+            let tx = TxEip4844 {
+                chain_id: self.cache.chain_id,
+                nonce: planned.nonce,
+                gas_limit: 21000,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                to: self.random_address(random),
+                value: U256::ZERO,
+                access_list: Default::default(),
+                blob_versioned_hashes,
+                max_fee_per_blob_gas,
+                input: Default::default(),
+            };
+
+            signing::blob_envelope(
+                TxEip4844WithSidecar { tx, sidecar },
+                self.fork(random).blob_form(self.config.blob_form),
+                &sender.sk,
+                random,
+            )
+        } else {
+            let tx = TxEip1559 {
+                chain_id: self.cache.chain_id,
+                nonce: planned.nonce,
+                gas_limit: 21000,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                to: self.random_address(random).into(),
+                value: U256::ZERO,
+                access_list: Default::default(),
+                input: Default::default(),
+            };
+
+            signing::envelope(tx, &sender.sk)
+        }
+    }
+
+    // Helper function to get the number of pending and queued transactions
+    // of the pool of the node
+    async fn pool_status(&self) -> Option<(u64, u64)> {
+        let status =
+            self.provider.client().request_noparams::<Value>("txpool_status").await.ok()?;
+        let count = |key: &str| {
+            let count = status.get(key)?.as_str()?;
+            u64::from_str_radix(count.trim_start_matches("0x"), 16).ok()
+        };
+
+        Some((count("pending")?, count("queued")?))
+    }
+
+    // Helper function to get the memory used by the node out of its metrics
+    // endpoint, if one is configured
+    async fn memory(&self) -> Option<u64> {
+        let url = self.config.metrics_url.clone()?;
+        let metrics = self.client.get(url).send().await.ok()?.text().await.ok()?;
+        parse_memory(&metrics)
+    }
+}
+
+#[test]
+fn test_scenario_plan() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let nonces = [
+        3, 7,
+    ];
+
+    let fees = Fees {
+        gas_price: 100,
+        max_fee_per_gas: 200,
+        max_priority_fee_per_gas: 100,
+        max_fee_per_blob_gas: 100,
+    };
+
+    let plan = Scenario::Replacement.plan(&nonces, fees, &mut random);
+    assert_eq!(plan.len(), REPLACEMENT_STORM_LENGTH);
+    assert!(plan.iter().all(|tx| tx.nonce == plan[0].nonce && tx.sender == plan[0].sender));
+
+    // Every replacement the pool accepts is at least 10% above the last one
+    // it accepted, even after many bumps and for fees of a single wei
+    for fees in [
+        fees,
+        Fees { max_priority_fee_per_gas: 1, ..fees },
+    ] {
+        let plan = Scenario::Replacement.plan(&nonces, fees, &mut random);
+        let mut accepted = plan[0].fees;
+        let mut bumps = 0;
+        for tx in &plan[1..] {
+            let above = |new: u128, old: u128| new * 100 >= old * 110;
+            let (tip, accepted_tip) =
+                (tx.fees.max_priority_fee_per_gas, accepted.max_priority_fee_per_gas);
+            assert!(above(tip, accepted_tip) || tip * 100 <= accepted_tip * 109);
+            if above(tip, accepted_tip) {
+                assert!(above(tx.fees.max_fee_per_gas, accepted.max_fee_per_gas));
+                accepted = tx.fees;
+                bumps += 1;
+            }
+        }
+        assert!(bumps > 3);
+    }
+
+    let plan = Scenario::Gaps.plan(&nonces, fees, &mut random);
+    assert_eq!(plan.len() as u64, 2 * GAP_CHAIN_LENGTH);
+    assert!(plan.iter().all(|tx| tx.nonce > nonces[tx.sender]));
+
+    let plan = Scenario::Eviction.plan(&nonces, fees, &mut random);
+    assert!(
+        plan.iter().filter(|tx| tx.sender == 1).all(|tx| tx.fees.max_priority_fee_per_gas >= 200)
+    );
+
+    let metrics =
+        "# TYPE process_resident_memory_bytes gauge\nprocess_resident_memory_bytes 1.048576e+06\n";
+    assert_eq!(parse_memory(metrics), Some(1048576));
+}
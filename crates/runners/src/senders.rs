@@ -0,0 +1,157 @@
+use crate::{Runner, config::RunnerConfig};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    signers::k256::ecdsa::SigningKey,
+};
+use common::{
+    constants::{
+        MIN_SENDER_BALANCE, SCENARIO_SENDERS, SENDER_FUNDING, SENDER_FUNDING_TIMEOUT, STATIC_KEYS,
+    },
+    parse_sk,
+    types::Backend,
+};
+use rand::{Rng, rngs::StdRng};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Sender is one of the accounts of the pool, with its nonce when the pool
+/// was built
+pub struct Sender {
+    pub sk: SigningKey,
+    pub address: Address,
+    pub nonce: u64,
+}
+
+/// SenderPool spreads the transactions of a runner across the accounts of
+/// `STATIC_KEYS`, so that they do not all contend for the nonce of a single
+/// account and the pending queues of the node get many different shapes
+#[derive(Default)]
+pub struct SenderPool {
+    senders: Vec<Sender>,
+    /// The index of the sender of the last transaction, if any
+    current: Option<usize>,
+}
+
+impl SenderPool {
+    /// Returns the number of accounts of the pool of a runner
+    ///
+    /// # Arguments
+    ///
+    /// * `runner` - The type of the runner.
+    /// * `config` - The config of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The size of the pool, zero for the runners that do not use one.
+    pub fn size(runner: Runner, config: &RunnerConfig) -> usize {
+        match runner {
+            Runner::Engine | Runner::Envelope => 0,
+            // Scenarios need several senders, as the pool limits are per account
+            Runner::Scenario if config.senders < 2 => SCENARIO_SENDERS,
+            _ => config.senders,
+        }
+    }
+
+    /// Funds the `size` static keys from `offset` that do not have enough balance,
+    /// either from the genesis or from a previous session, from the faucet,
+    /// that is, the key the provider signs with. It is called once per runner
+    /// instance, before its workers are spawned, and waits up to
+    /// `SENDER_FUNDING_TIMEOUT` seconds for the funding to be mined, so that
+    /// the workers read the nonces and balances that follow it.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider of the faucet.
+    /// * `offset` - The first static key of the accounts.
+    /// * `size` - The number of accounts, from `offset` up to the number of
+    ///   static keys.
+    pub async fn fund(provider: &Backend, offset: usize, size: usize) {
+        let mut pending = vec![];
+        for key in STATIC_KEYS.iter().skip(offset).take(size) {
+            let address = Address::from_private_key(&parse_sk(key).unwrap());
+            let balance = provider.get_balance(address).await.unwrap_or_default();
+
+            // The transactions are sent one after the other from the same
+            // task, so they get consecutive nonces of the faucet
+            if balance < U256::from(MIN_SENDER_BALANCE) {
+                let request =
+                    TransactionRequest::default().to(address).value(U256::from(SENDER_FUNDING));
+                if let Ok(tx) = provider.send_transaction(request).await {
+                    pending.push(tx);
+                }
+            }
+        }
+
+        let _ = timeout(Duration::from_secs(SENDER_FUNDING_TIMEOUT), async {
+            for tx in pending {
+                let _ = tx.get_receipt().await;
+            }
+        })
+        .await;
+    }
+
+    /// Builds a pool out of the `size` static keys from `offset`, with their nonces
+    /// as the node reports them. The accounts are expected to have been
+    /// funded with `fund` already.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider of the runner.
+    /// * `offset` - The first static key of the pool.
+    /// * `size` - The number of accounts of the pool, from `offset` up to the
+    ///   number of static keys.
+    ///
+    /// # Returns
+    ///
+    /// The pool, empty if `size` is zero.
+    pub async fn new(provider: &Backend, offset: usize, size: usize) -> Self {
+        let mut senders = vec![];
+
+        for key in STATIC_KEYS.iter().skip(offset).take(size) {
+            let sk = parse_sk(key).unwrap();
+            let address = Address::from_private_key(&sk);
+            let account = provider.get_account(address).await.unwrap_or_default();
+
+            senders.push(Sender { sk, address, nonce: account.nonce });
+        }
+
+        Self { senders, current: None }
+    }
+
+    /// Returns whether the pool has no accounts, in which case the runner
+    /// sends from its own key
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Returns the accounts of the pool
+    pub fn senders(&self) -> &[Sender] {
+        &self.senders
+    }
+
+    /// Picks the sender of the next transaction at random
+    pub fn pick(&mut self, random: &mut StdRng) -> Option<&Sender> {
+        if self.senders.is_empty() {
+            return None;
+        }
+
+        let index = random.random_range(0..self.senders.len());
+        self.current = Some(index);
+        Some(&self.senders[index])
+    }
+
+    /// Returns the index of the sender of the last transaction, if any
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Makes the sender at `index` the one of the last transaction, as when
+    /// restoring a runner from a checkpoint
+    pub fn select(&mut self, index: usize) -> Option<&Sender> {
+        let sender = self.senders.get(index)?;
+        self.current = Some(index);
+        Some(sender)
+    }
+}
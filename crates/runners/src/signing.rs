@@ -0,0 +1,379 @@
+use crate::cells;
+use alloy::{
+    consensus::{
+        SignableTransaction, TxEip1559, TxEip2930, TxEip4844Variant, TxEip4844WithSidecar,
+        TxEip7702, TxEnvelope, TxLegacy, Typed2718,
+        transaction::{RlpEcdsaDecodableTx, RlpEcdsaEncodableTx},
+    },
+    eips::eip2718::Encodable2718,
+    network::TxSignerSync,
+    primitives::{Signature, U256},
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
+};
+use alloy_rlp::{Encodable, Header};
+use rand::{Rng, rngs::StdRng};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// The order of the secp256k1 curve. Signatures with `r` or `s` not below it
+/// are invalid, and so are those with `s` above half of it since EIP-2
+pub const SECP256K1N: U256 = U256::from_limbs([
+    0xBFD25E8CD0364141,
+    0xBAAEDCE6AF48A03B,
+    0xFFFFFFFFFFFFFFFE,
+    0xFFFFFFFFFFFFFFFF,
+]);
+
+/// Signing selects what is done with the signature of mutated transactions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signing {
+    /// The mutated bytes are sent as they are, without any valid signature
+    None,
+    /// The mutated bytes are decoded back into a transaction when possible,
+    /// which is then signed with the key of the runner
+    Resign,
+    /// The transaction is left intact and only its signature is mutated
+    Signature,
+}
+
+impl Display for Signing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Signing::None => write!(f, "none"),
+            Signing::Resign => write!(f, "resign"),
+            Signing::Signature => write!(f, "signature"),
+        }
+    }
+}
+
+impl FromStr for Signing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Signing::None),
+            "resign" => Ok(Signing::Resign),
+            "signature" => Ok(Signing::Signature),
+            _ => Err(format!("invalid signing: {}", s)),
+        }
+    }
+}
+
+/// BlobForm selects how blob transactions are encoded into envelopes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobForm {
+    /// The network form of EIP-4844, with one proof per blob
+    Network,
+    /// The consensus form, without the sidecar
+    Consensus,
+    /// The network form of EIP-7594, with a version byte and cell proofs
+    Cells,
+    /// Any of the above, picked at random for each transaction
+    Mixed,
+}
+
+impl Display for BlobForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobForm::Network => write!(f, "network"),
+            BlobForm::Consensus => write!(f, "consensus"),
+            BlobForm::Cells => write!(f, "cells"),
+            BlobForm::Mixed => write!(f, "mixed"),
+        }
+    }
+}
+
+impl FromStr for BlobForm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "network" => Ok(BlobForm::Network),
+            "consensus" => Ok(BlobForm::Consensus),
+            "cells" => Ok(BlobForm::Cells),
+            "mixed" => Ok(BlobForm::Mixed),
+            _ => Err(format!("invalid blob_form: {}", s)),
+        }
+    }
+}
+
+/// Resignable is implemented by the transaction types the runners generate,
+/// so that they can be signed into an envelope and decoded back from it
+pub trait Resignable:
+    SignableTransaction<Signature> + RlpEcdsaEncodableTx + RlpEcdsaDecodableTx
+{
+    /// Wraps the signed transaction into an envelope
+    fn into_envelope(self, signature: Signature) -> TxEnvelope;
+
+    /// Decodes the transaction from a signed EIP-2718 envelope, dropping the
+    /// signature
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Self::eip2718_decode(&mut &bytes[..]).ok().map(|signed| signed.into_parts().0)
+    }
+
+    /// Encodes the transaction with the given raw signature values, which do
+    /// not need to make up a valid signature
+    fn encode_raw(&self, v: U256, r: U256, s: U256) -> Vec<u8> {
+        let mut out = vec![];
+        if !self.is_legacy() {
+            out.push(self.ty());
+        }
+        encode_fields_raw(self, v, r, s, &mut out);
+        out
+    }
+}
+
+impl Resignable for TxLegacy {
+    fn into_envelope(self, signature: Signature) -> TxEnvelope {
+        self.into_signed(signature).into()
+    }
+
+    // Legacy envelopes are plain RLP lists, without a type byte
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Self::rlp_decode_signed(&mut &bytes[..]).ok().map(|signed| signed.into_parts().0)
+    }
+}
+
+impl Resignable for TxEip2930 {
+    fn into_envelope(self, signature: Signature) -> TxEnvelope {
+        self.into_signed(signature).into()
+    }
+}
+
+impl Resignable for TxEip1559 {
+    fn into_envelope(self, signature: Signature) -> TxEnvelope {
+        self.into_signed(signature).into()
+    }
+}
+
+impl Resignable for TxEip7702 {
+    fn into_envelope(self, signature: Signature) -> TxEnvelope {
+        self.into_signed(signature).into()
+    }
+}
+
+impl Resignable for TxEip4844Variant {
+    fn into_envelope(self, signature: Signature) -> TxEnvelope {
+        self.into_signed(signature).into()
+    }
+
+    // In the network form, the signature goes inside the transaction list,
+    // which is followed by the sidecar
+    fn encode_raw(&self, v: U256, r: U256, s: U256) -> Vec<u8> {
+        let mut out = vec![self.ty()];
+        match self {
+            TxEip4844Variant::TxEip4844(tx) => encode_fields_raw(tx, v, r, s, &mut out),
+            TxEip4844Variant::TxEip4844WithSidecar(tx) => {
+                // The sidecar is encoded right after the transaction list
+                let mut fields = vec![];
+                tx.rlp_encode_fields(&mut fields);
+                let sidecar = &fields[tx.tx.rlp_encoded_length()..];
+
+                let mut inner = vec![];
+                encode_fields_raw(&tx.tx, v, r, s, &mut inner);
+                inner.extend_from_slice(sidecar);
+
+                Header { list: true, payload_length: inner.len() }.encode(&mut out);
+                out.extend_from_slice(&inner);
+            }
+        }
+        out
+    }
+}
+
+/// Signs a transaction with the key of the runner and encodes it as an
+/// EIP-2718 envelope, which is what `eth_sendRawTransaction` expects
+///
+/// # Arguments
+///
+/// * `tx` - The transaction to sign.
+/// * `sk` - The signing key of the runner.
+///
+/// # Returns
+///
+/// The signed envelope, or empty bytes if the transaction cannot be signed.
+pub fn envelope<T: Resignable>(mut tx: T, sk: &SigningKey) -> Vec<u8> {
+    let signer = PrivateKeySigner::from_signing_key(sk.clone());
+    match signer.sign_transaction_sync(&mut tx) {
+        Ok(signature) => tx.into_envelope(signature).encoded_2718(),
+        Err(_) => vec![],
+    }
+}
+
+/// Signs a blob transaction and encodes it in the given envelope form
+///
+/// # Arguments
+///
+/// * `tx` - The blob transaction to sign, with its sidecar.
+/// * `form` - The envelope form selected in the config of the runner.
+/// * `sk` - The signing key of the runner.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The signed envelope, or empty bytes if the transaction cannot be signed.
+pub fn blob_envelope(
+    tx: TxEip4844WithSidecar,
+    form: BlobForm,
+    sk: &SigningKey,
+    random: &mut StdRng,
+) -> Vec<u8> {
+    match form {
+        BlobForm::Network => envelope(TxEip4844Variant::TxEip4844WithSidecar(tx), sk),
+        BlobForm::Consensus => envelope(TxEip4844Variant::TxEip4844(tx.tx), sk),
+        BlobForm::Cells => cells::envelope(tx, sk, random),
+        BlobForm::Mixed => {
+            let forms = [
+                BlobForm::Network,
+                BlobForm::Consensus,
+                BlobForm::Cells,
+            ];
+            let form = forms[random.random_range(0..forms.len())];
+            blob_envelope(tx, form, sk, random)
+        }
+    }
+}
+
+/// Signs the mutated bytes or mutates their signature, depending on the mode
+///
+/// # Arguments
+///
+/// * `signing` - The signing mode of the runner.
+/// * `bytes` - The bytes the runner mutates.
+/// * `sk` - The signing key of the runner.
+/// * `random` - The random number generator of the runner.
+///
+/// # Returns
+///
+/// The signed EIP-2718 envelope, or `None` if the mode is `none` or the bytes
+/// cannot be decoded back into a transaction of type `T`.
+pub fn sign_mutated<T: Resignable>(
+    signing: Signing,
+    bytes: &[u8],
+    sk: &SigningKey,
+    random: &mut StdRng,
+) -> Option<Vec<u8>> {
+    if signing == Signing::None {
+        return None;
+    }
+
+    let mut tx = T::decode(bytes)?;
+    if signing == Signing::Resign {
+        return Some(envelope(tx, sk));
+    }
+
+    let signer = PrivateKeySigner::from_signing_key(sk.clone());
+    let signature = signer.sign_transaction_sync(&mut tx).ok()?;
+    let (v, r, s) = mutate_signature(&tx, &signature, random);
+
+    Some(tx.encode_raw(v, r, s))
+}
+
+/// Same as `sign_mutated`, for runners whose bytes may hold a transaction of
+/// any type
+pub fn sign_mutated_any(
+    signing: Signing,
+    bytes: &[u8],
+    sk: &SigningKey,
+    random: &mut StdRng,
+) -> Option<Vec<u8>> {
+    sign_mutated::<TxLegacy>(signing, bytes, sk, random)
+        .or_else(|| sign_mutated::<TxEip2930>(signing, bytes, sk, random))
+        .or_else(|| sign_mutated::<TxEip1559>(signing, bytes, sk, random))
+        .or_else(|| sign_mutated::<TxEip4844Variant>(signing, bytes, sk, random))
+        .or_else(|| sign_mutated::<TxEip7702>(signing, bytes, sk, random))
+}
+
+// Helper function to turn a valid signature into raw `v`, `r` and `s` values
+// that are invalid in one way or another
+fn mutate_signature<T: Resignable>(
+    tx: &T,
+    signature: &Signature,
+    random: &mut StdRng,
+) -> (U256, U256, U256) {
+    let r = signature.r();
+    let s = signature.s();
+    let parity = signature.v();
+
+    // Legacy transactions encode the chain id in `v` since EIP-155
+    let v = |parity: bool| match tx.chain_id() {
+        Some(chain_id) if tx.is_legacy() => {
+            U256::from(chain_id) * U256::from(2) + U256::from(35 + parity as u64)
+        }
+        None if tx.is_legacy() => U256::from(27 + parity as u64),
+        _ => U256::from(parity as u64),
+    };
+
+    match random.random_range(0..10) {
+        // Wrong parity, which recovers another sender
+        0 => (v(!parity), r, s),
+        // The malleable twin of the signature, with high `s`
+        1 => (v(!parity), r, SECP256K1N - s),
+        2 => (v(parity), U256::ZERO, s),
+        3 => (v(parity), r, U256::ZERO),
+        // `r` and `s` not below the order of the curve
+        4 => (v(parity), r.saturating_add(SECP256K1N), s),
+        5 => (v(parity), r, s.saturating_add(SECP256K1N)),
+        6 => (v(parity), U256::MAX, U256::MAX),
+        // `v` out of range for the transaction type
+        7 => {
+            let v = match random.random_range(0..4) {
+                0 if tx.is_legacy() => U256::from(27 + parity as u64),
+                1 if tx.is_legacy() => U256::from(parity as u64),
+                0 | 1 => U256::from(27 + parity as u64),
+                2 => U256::from(random.random_range(2..=u64::MAX)),
+                _ => v(parity) + U256::from(2),
+            };
+            (v, r, s)
+        }
+        8 => (U256::from_be_bytes(random.random::<[u8; 32]>()), r, s),
+        _ => (
+            v(random.random_bool(0.5)),
+            U256::from_be_bytes(random.random::<[u8; 32]>()),
+            U256::from_be_bytes(random.random::<[u8; 32]>()),
+        ),
+    }
+}
+
+// Helper function to encode the list of fields of a transaction followed by
+// the given signature values
+fn encode_fields_raw<T: RlpEcdsaEncodableTx>(tx: &T, v: U256, r: U256, s: U256, out: &mut Vec<u8>) {
+    let payload_length = tx.rlp_encoded_fields_length() + v.length() + r.length() + s.length();
+    Header { list: true, payload_length }.encode(out);
+    tx.rlp_encode_fields(out);
+    v.encode(out);
+    r.encode(out);
+    s.encode(out);
+}
+
+#[test]
+fn test_sign_mutated() {
+    use rand::SeedableRng;
+
+    let mut random = StdRng::seed_from_u64(1);
+    let sk = SigningKey::from_slice(&[1u8; 32]).unwrap();
+    let tx = TxEip1559 { chain_id: 1, ..Default::default() };
+    let bytes = envelope(tx.clone(), &sk);
+    assert_eq!(TxEip1559::decode(&bytes), Some(tx.clone()));
+
+    let signed = sign_mutated::<TxEip1559>(Signing::Resign, &bytes, &sk, &mut random).unwrap();
+    assert_eq!(signed, bytes);
+
+    let mutated = sign_mutated::<TxEip1559>(Signing::Signature, &bytes, &sk, &mut random).unwrap();
+    assert_eq!(mutated[0], tx.ty());
+    assert!(sign_mutated::<TxEip1559>(Signing::None, &bytes, &sk, &mut random).is_none());
+}
+
+#[test]
+fn test_legacy_envelope() {
+    let sk = SigningKey::from_slice(&[1u8; 32]).unwrap();
+    for chain_id in [
+        None,
+        Some(1),
+    ] {
+        let tx = TxLegacy { chain_id, ..Default::default() };
+        assert_eq!(TxLegacy::decode(&envelope(tx.clone(), &sk)), Some(tx));
+    }
+}
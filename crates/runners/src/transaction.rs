@@ -0,0 +1,326 @@
+use crate::{
+    Runner, batch,
+    builder::Builder,
+    cache::BuilderCache,
+    checkpoint::Checkpoint,
+    config::RunnerConfig,
+    control::{Outcome, Session, Worker},
+    fixtures,
+    fork::Fork,
+    methods::{self, SEND},
+    nonces::{Fees, NonceManager},
+    senders::SenderPool,
+    signing::Signing,
+};
+use alloy::{
+    primitives::{Address, TxHash},
+    providers::Provider,
+    rpc::types::{TransactionInput, TransactionRequest},
+    signers::k256::ecdsa::SigningKey,
+    transports::http::reqwest::Url,
+};
+use common::{connect, types::Backend};
+use rand::{Rng, rngs::StdRng};
+use std::{marker::PhantomData, mem};
+
+/// Transaction is the type of transaction a `TransactionRunner` generates,
+/// which is all that differs between the runners sending transactions
+#[allow(async_fn_in_trait)]
+pub trait Transaction: Sized {
+    /// The type of the runner, written in its checkpoints
+    const RUNNER: Runner;
+
+    /// Builds a new transaction and signs it with the key of the runner
+    ///
+    /// # Arguments
+    ///
+    /// * `runner` - The runner generating the transaction.
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The request of the transaction and its signed EIP-2718 envelope.
+    async fn create(
+        runner: &TransactionRunner<Self>,
+        random: &mut StdRng,
+    ) -> (TransactionRequest, Vec<u8>);
+
+    /// Signs the mutated bytes again, or mutates their signature, as
+    /// `signing::sign_mutated` does for transactions of this type
+    fn sign_mutated(
+        signing: Signing,
+        bytes: &[u8],
+        sk: &SigningKey,
+        random: &mut StdRng,
+    ) -> Option<Vec<u8>>;
+}
+
+/// TransactionRunner generates transactions of type `T` and mutates their
+/// envelopes, sending them through the methods, batches, senders and nonce
+/// patterns of its config.
+pub struct TransactionRunner<T> {
+    pub session: Session,
+    pub sk: SigningKey,
+    pub provider: Backend,
+    pub cache: BuilderCache,
+    pub sender: Address,
+    pub config: RunnerConfig,
+    pub pending_batch: Vec<Vec<u8>>,
+    pub senders: SenderPool,
+    pub nonces: NonceManager,
+    transaction: PhantomData<T>,
+}
+
+impl<T: Transaction> Builder for TransactionRunner<T> {
+    fn provider(&self) -> &Backend {
+        &self.provider
+    }
+
+    fn cache(&self) -> &BuilderCache {
+        &self.cache
+    }
+
+    fn cache_mut(&mut self) -> &mut BuilderCache {
+        &mut self.cache
+    }
+
+    fn sender(&self) -> Address {
+        self.sender
+    }
+
+    fn config(&self) -> &RunnerConfig {
+        &self.config
+    }
+}
+
+impl<T: Transaction> TransactionRunner<T> {
+    pub async fn new(
+        name: &str,
+        rpc_url: Url,
+        sk: SigningKey,
+        seed: u64,
+        max_operations_per_mutation: u64,
+        config: RunnerConfig,
+    ) -> Self {
+        // The transport is chosen by the scheme of the URL. The app checks the
+        // connection before spawning the runner, so this should not fail
+        let provider = connect(&rpc_url, &sk).await.unwrap();
+
+        let sender = Address::from_private_key(&sk);
+        let contracts = fixtures::deploy(&provider, &rpc_url, sender, &config).await;
+        let account = provider.get_account(sender).await.unwrap_or_default();
+        let mut cache = BuilderCache {
+            gas_price: provider.get_gas_price().await.unwrap_or_default(),
+            max_priority_fee: provider.get_max_priority_fee_per_gas().await.unwrap_or_default(),
+            max_fee_per_blob_gas: provider.get_blob_base_fee().await.unwrap_or_default(),
+            balance: account.balance,
+            nonce: account.nonce,
+            chain_id: provider.get_chain_id().await.unwrap_or_default(),
+            contracts,
+            ..Default::default()
+        };
+        cache.update_block(&provider).await;
+        cache.fork = Fork::resolve(config.fork, &provider).await;
+
+        let session = Session::new(name, seed, max_operations_per_mutation, &config);
+        let senders =
+            SenderPool::new(&provider, config.sender_offset, SenderPool::size(T::RUNNER, &config))
+                .await;
+        let mut nonces = NonceManager::default();
+        nonces.track(sender, account.nonce);
+
+        Self {
+            session,
+            sk,
+            provider,
+            cache,
+            sender,
+            config,
+            pending_batch: vec![],
+            senders,
+            nonces,
+            transaction: PhantomData,
+        }
+    }
+
+    /// Generates a new transaction, which becomes the one being mutated
+    ///
+    /// # Arguments
+    ///
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The request of the transaction, for the methods that take one.
+    pub async fn generate(&mut self, random: &mut StdRng) -> TransactionRequest {
+        // Generated transactions are spread across the accounts of the sender
+        // pool, if any
+        if let Some(sender) = self.senders.pick(random) {
+            self.sk = sender.sk.clone();
+            self.sender = sender.address;
+            self.nonces.track(sender.address, sender.nonce);
+        }
+
+        // 50% chance to update the cache
+        // This is to try to get further in the execution by bypassing
+        // common checks like gas > expected and so on
+        if random.random_bool(0.5) {
+            self.cache.update(&self.provider, self.sender).await;
+            self.nonces.sync(self.sender, self.cache.nonce);
+        }
+
+        // The nonce follows one of the patterns of the mix, and the nonces
+        // sent before are replaced, or fail to be, by scaling the fees of the
+        // last transaction sent with them
+        let (nonce, pattern) = self.nonces.next(self.sender, &self.config.nonces, random);
+        let fees = self.nonces.fees(self.sender, nonce);
+        let cache = self.cache.clone();
+        self.cache.nonce = nonce;
+        self.cache.pinned_nonce = true;
+        self.cache.pinned_fees = fees.and_then(|fees| pattern.adjust_fees(fees, random));
+
+        let (request, tx) = T::create(self, random).await;
+        self.cache = cache;
+        self.nonces.record(self.sender, nonce, pattern, Fees::from(&request));
+        self.session.current_tx = tx;
+
+        request
+    }
+
+    /// Mutates the transaction being mutated
+    ///
+    /// # Arguments
+    ///
+    /// * `random` - The random number generator of the runner.
+    ///
+    /// # Returns
+    ///
+    /// The payload to send, which is signed again or has its signature
+    /// mutated whenever it can be decoded back into a transaction.
+    pub fn mutate(&mut self, random: &mut StdRng) -> Vec<u8> {
+        // Mutating only the signature leaves the transaction intact
+        if self.config.signing != Signing::Signature {
+            self.session.mutator.mutate(&mut self.session.current_tx);
+        }
+
+        T::sign_mutated(self.config.signing, &self.session.current_tx, &self.sk, random)
+            .unwrap_or_else(|| self.session.current_tx.clone())
+    }
+
+    // Helper function to send a generated transaction through one of the
+    // methods of the config
+    async fn send_generated(&mut self, request: TransactionRequest, random: &mut StdRng) {
+        let provider = self.provider.clone();
+        let payload = self.session.current_tx.clone();
+        let method = self.config.method(random);
+        if method == SEND {
+            // The node does not know the keys of the sender pool, so their
+            // transactions are sent already signed
+            let raw = (!self.senders.is_empty()).then(|| payload.clone());
+            self.session
+                .pipeline
+                .submit(payload, async move {
+                    match raw {
+                        Some(raw) => provider
+                            .client()
+                            .request::<_, TxHash>("eth_sendRawTransaction", &raw)
+                            .await
+                            .map(|_| ()),
+                        None => provider.send_transaction_unsafe(request).await.map(|_| ()),
+                    }
+                })
+                .await;
+        } else {
+            let params = methods::params(method, &request, random);
+            self.session.pipeline.submit(payload, methods::call(provider, method, params)).await;
+        }
+    }
+
+    // Helper function to send a mutated transaction, either packed into a
+    // batch or through one of the methods of the config
+    async fn send_mutated(&mut self, payload: Vec<u8>, random: &mut StdRng) {
+        let provider = self.provider.clone();
+        if self.config.batch > 0 {
+            // Mutated transactions are packed into a single JSON-RPC batch once
+            // there are enough of them
+            self.pending_batch.push(payload);
+            if self.pending_batch.len() >= self.config.batch {
+                let packet = batch::build(&self.pending_batch, random);
+                let payload = serde_json::to_vec(&packet).unwrap_or_default();
+                let transport = provider.client().transport().clone();
+                self.pending_batch.clear();
+                self.session.pipeline.submit(payload, batch::send(transport, packet)).await;
+            }
+            return;
+        }
+
+        let method = self.config.method(random);
+        if method == SEND {
+            self.session
+                .pipeline
+                .submit(payload.clone(), async move {
+                    provider
+                        .client()
+                        .request::<_, TxHash>("eth_sendRawTransaction", &payload)
+                        .await
+                        .map(|_| ())
+                })
+                .await;
+        } else {
+            // The mutated bytes are executed as the calldata of a call
+            let request = TransactionRequest {
+                from: Some(self.sender),
+                to: Some(self.to(random)),
+                input: TransactionInput::new(payload.clone().into()),
+                ..Default::default()
+            };
+            let params = methods::params(method, &request, random);
+            self.session.pipeline.submit(payload, methods::call(provider, method, params)).await;
+        }
+    }
+}
+
+impl<T: Transaction> Worker for TransactionRunner<T> {
+    const RUNNER: Runner = T::RUNNER;
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    async fn step(&mut self, random: &mut StdRng) -> Outcome {
+        // 10% chance to re-generate the transaction
+        if random.random_bool(0.1) || self.session.current_tx.is_empty() {
+            let request = self.generate(random).await;
+            self.send_generated(request, random).await;
+        } else {
+            let payload = self.mutate(random);
+            self.send_mutated(payload, random).await;
+        }
+
+        Outcome::Sent
+    }
+
+    fn save(&self, checkpoint: &mut Checkpoint) {
+        checkpoint.cache = self.cache.clone();
+        checkpoint.pending_batch = self.pending_batch.clone();
+        checkpoint.sender = self.senders.current();
+        checkpoint.nonces = self.nonces.clone();
+    }
+
+    fn load(&mut self, checkpoint: &mut Checkpoint) {
+        self.cache = mem::take(&mut checkpoint.cache);
+        self.pending_batch = mem::take(&mut checkpoint.pending_batch);
+        self.nonces = mem::take(&mut checkpoint.nonces);
+
+        // The runner keeps sending from the account of the pool it was sending
+        // from, instead of its own key
+        if let Some(sender) = checkpoint.sender.and_then(|index| self.senders.select(index)) {
+            self.sk = sender.sk.clone();
+            self.sender = sender.address;
+        }
+    }
+}